@@ -33,39 +33,101 @@ struct Args {
     /// Enable checkpoint calls
     #[arg(long)]
     with_checkpoints: bool,
+
+    /// Significant decimal digits of precision to retain within each
+    /// power-of-two magnitude (HdrHistogram-style). Higher values trade
+    /// memory for resolution; 2 resolves every magnitude to ~1%.
+    #[arg(long, default_value_t = 2)]
+    significant_digits: u32,
 }
 
+/// Number of power-of-two magnitudes of microsecond latency to cover.
+/// `u64::MAX` microseconds is far beyond any latency this benchmark could
+/// observe, so 64 magnitudes leaves headroom without bounding the range.
+const NUM_MAGNITUDES: usize = 64;
+
+/// HdrHistogram-style latency histogram: each power-of-two magnitude is
+/// subdivided into a fixed number of linear sub-buckets, so - unlike a bare
+/// log2 histogram - percentiles within a magnitude are resolved to
+/// `significant_digits` decimal digits instead of being rounded up to the
+/// next power of two. Still lock-free (one `AtomicU64` per bucket) and
+/// fixed-memory (`NUM_MAGNITUDES * sub_buckets_per_magnitude` counters).
 struct LatencyHistogram {
-    buckets: [AtomicU64; 32],
+    buckets: Box<[AtomicU64]>,
+    sub_buckets_per_magnitude: u64,
 }
 
 impl LatencyHistogram {
-    fn new() -> Self {
+    fn new(significant_digits: u32) -> Self {
+        let sub_buckets_per_magnitude = 10u64.saturating_pow(significant_digits).next_power_of_two();
+        let total_buckets = NUM_MAGNITUDES * sub_buckets_per_magnitude as usize;
         Self {
-            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            buckets: (0..total_buckets).map(|_| AtomicU64::new(0)).collect(),
+            sub_buckets_per_magnitude,
+        }
+    }
+
+    /// Map a latency value to its (magnitude, sub-bucket within magnitude).
+    /// Magnitude `m` (for `m >= 1`) covers the value range
+    /// `[2^m, 2^(m+1) - 1]`; magnitude 0 covers values 0 and 1, which
+    /// share its first sub-bucket.
+    fn bucket_index(&self, value: u64) -> (usize, usize) {
+        if value == 0 {
+            return (0, 0);
+        }
+
+        let magnitude = (63 - value.leading_zeros()) as usize;
+        let range_start = 1u64 << magnitude;
+        let range_size = range_start;
+        let offset = value - range_start;
+
+        let sub_bucket = if range_size <= self.sub_buckets_per_magnitude {
+            offset
+        } else {
+            offset * self.sub_buckets_per_magnitude / range_size
+        };
+
+        (magnitude, sub_bucket as usize)
+    }
+
+    /// Highest value that maps into `(magnitude, sub_bucket)`, used to
+    /// report a percentile without underselling it.
+    fn value_for_bucket(&self, magnitude: usize, sub_bucket: usize) -> u64 {
+        let range_start = 1u64 << magnitude;
+        let range_size = range_start;
+
+        if range_size <= self.sub_buckets_per_magnitude {
+            range_start + sub_bucket as u64
+        } else {
+            let width = range_size / self.sub_buckets_per_magnitude;
+            range_start + (sub_bucket as u64 + 1) * width - 1
         }
     }
 
     fn record(&self, latency_us: u64) {
-        // Bucket index: log2(latency_us + 1), clamped to 31
-        let bucket = (64 - (latency_us + 1).leading_zeros()).min(31) as usize;
-        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        let (magnitude, sub_bucket) = self.bucket_index(latency_us);
+        let idx = magnitude * self.sub_buckets_per_magnitude as usize + sub_bucket;
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
     }
 
     fn percentile(&self, p: f64) -> u64 {
-        let total: u64 = self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum();
+        let total = self.total();
+        if total == 0 {
+            return 0;
+        }
 
-        let target = (total as f64 * p / 100.0) as u64;
+        let target = ((total as f64 * p / 100.0).ceil() as u64).max(1);
+        let spb = self.sub_buckets_per_magnitude as usize;
         let mut count = 0u64;
 
-        for (i, bucket) in self.buckets.iter().enumerate() {
+        for (idx, bucket) in self.buckets.iter().enumerate() {
             count += bucket.load(Ordering::Relaxed);
             if count >= target {
-                return 1u64 << i;
+                return self.value_for_bucket(idx / spb, idx % spb);
             }
         }
 
-        1 << 31
+        self.value_for_bucket(NUM_MAGNITUDES - 1, spb - 1)
     }
 
     fn total(&self) -> u64 {
@@ -86,7 +148,7 @@ fn main() {
     tracing::info!("  Checkpoints enabled: {}", args.with_checkpoints);
 
     let stop = Arc::new(AtomicBool::new(false));
-    let histogram = Arc::new(LatencyHistogram::new());
+    let histogram = Arc::new(LatencyHistogram::new(args.significant_digits));
     let total_ops = Arc::new(AtomicU64::new(0));
     let checkpoint_yields = Arc::new(AtomicU64::new(0));
 
@@ -126,10 +188,17 @@ fn main() {
         let handle = thread::Builder::new()
             .name(format!("worker-{}", i))
             .spawn(move || {
-                let mut last_op = Instant::now();
+                // `intended_start` is when this op *should* have begun, per
+                // the target rate, regardless of when the previous op
+                // actually finished. Measuring latency from here instead of
+                // from the real start is what avoids coordinated omission:
+                // a worker that falls behind (e.g. a scheduling stall)
+                // keeps stretching this against the wall clock instead of
+                // quietly resetting its baseline every iteration.
+                let mut intended_start = Instant::now();
 
                 while !stop_clone.load(Ordering::Relaxed) {
-                    let start = Instant::now();
+                    let service_start = Instant::now();
 
                     // Simulate work
                     let mut sum: u64 = 0;
@@ -144,16 +213,33 @@ fn main() {
                         thread::yield_now();
                     }
 
-                    let elapsed = start.elapsed();
-                    hist_clone.record(elapsed.as_micros() as u64);
+                    // Total latency relative to when the op was supposed to
+                    // start, not to when it actually started.
+                    let total_latency =
+                        service_start.duration_since(intended_start) + service_start.elapsed();
+                    hist_clone.record(total_latency.as_micros() as u64);
                     ops_clone.fetch_add(1, Ordering::Relaxed);
 
-                    // Rate limiting
-                    let since_last = start.duration_since(last_op);
-                    if since_last < interval {
-                        thread::sleep(interval - since_last);
+                    // Backfill: if we're running behind by whole intervals,
+                    // synthesize the latency samples the missed ops would
+                    // have recorded, each one interval's worth less than the
+                    // last. These feed the histogram only, not `total_ops` -
+                    // no op actually ran for them - so a stall still shows
+                    // up in the tail instead of being hidden by the rate
+                    // limiter quietly issuing fewer real ops.
+                    if total_latency > interval {
+                        let mut missing = total_latency - interval;
+                        while missing >= interval {
+                            hist_clone.record(missing.as_micros() as u64);
+                            missing -= interval;
+                        }
+                    }
+
+                    intended_start += interval;
+                    let now = Instant::now();
+                    if now < intended_start {
+                        thread::sleep(intended_start - now);
                     }
-                    last_op = Instant::now();
                 }
             })
             .unwrap();
@@ -0,0 +1,101 @@
+//! Concurrency stress test for `MorpheusMetrics`
+//!
+//! Spawns many threads hammering `record_hint`/`record_escalation`/
+//! `record_defensive_mode`/`record_ack_latency` while a separate thread
+//! repeatedly calls `render()`, to prove the sharded per-worker counters and
+//! the mutex-guarded P² latency estimators are race-free. Intended to be run
+//! under ThreadSanitizer:
+//!
+//! ```sh
+//! RUSTFLAGS="-Z sanitizer=thread" \
+//!   TSAN_OPTIONS="suppressions=morpheus-bench/tsan_suppressions.txt" \
+//!   cargo +nightly run -Z build-std --target x86_64-unknown-linux-gnu \
+//!     --release --bin metrics_tsan
+//! ```
+//!
+//! Plain `cargo run --bin metrics_tsan` (no sanitizer) also works as a
+//! quick smoke test - it just won't catch data races on its own.
+
+use clap::Parser;
+use morpheus_runtime::MorpheusMetrics;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Hammer MorpheusMetrics from many threads concurrently
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// Number of recorder threads
+    #[arg(short = 'w', long, default_value_t = 8)]
+    recorders: usize,
+
+    /// Number of workers (worker IDs) each recorder cycles through
+    #[arg(long, default_value_t = 16)]
+    workers: u32,
+
+    /// Duration to run the stress test (seconds)
+    #[arg(short, long, default_value_t = 5)]
+    duration: u64,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    tracing_subscriber::fmt().with_env_filter("info").init();
+    tracing::info!("Metrics concurrency stress test");
+    tracing::info!("  Recorders: {}", args.recorders);
+    tracing::info!("  Workers: {}", args.workers);
+    tracing::info!("  Duration: {}s", args.duration);
+
+    let metrics = Arc::new(MorpheusMetrics::new());
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let mut handles = Vec::new();
+
+    for r in 0..args.recorders {
+        let metrics = metrics.clone();
+        let stop = stop.clone();
+        let num_workers = args.workers;
+        handles.push(thread::spawn(move || {
+            let mut i: u64 = 0;
+            while !stop.load(Ordering::Relaxed) {
+                let worker_id = (r as u64 + i) as u32 % num_workers;
+                metrics.record_hint(worker_id, ["budget", "pressure", "imbalance", "deadline"][i as usize % 4]);
+                metrics.record_defensive_mode(worker_id);
+                metrics.record_ack_latency(worker_id, i % 1_000_000);
+                if i % 7 == 0 {
+                    metrics.record_escalation(["thread_kick", "signal", "none"][i as usize % 3]);
+                }
+                if i % 11 == 0 {
+                    metrics.record_hint_drop();
+                }
+                i = i.wrapping_add(1);
+            }
+        }));
+    }
+
+    // Renderer thread: concurrent readers exercise the same snapshots the
+    // recorders are mutating.
+    {
+        let metrics = metrics.clone();
+        let stop = stop.clone();
+        handles.push(thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let rendered = metrics.render();
+                std::hint::black_box(&rendered);
+            }
+        }));
+    }
+
+    thread::sleep(Duration::from_secs(args.duration));
+    stop.store(true, Ordering::Release);
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    tracing::info!("No data races detected (run under TSAN to actually verify)");
+    tracing::info!("Final render:\n{}", metrics.render());
+}
@@ -13,10 +13,26 @@
 //! - **Language-neutral**: Operates at worker-thread level, not async task level
 //! - **No pointers cross boundary**: SCB contains only integers
 //! - **Cache-aligned**: SCB is 128 bytes (2 cache lines) for optimal performance
+//!
+//! ## Loom model checking
+//!
+//! The kernel↔runtime handshake on `preempt_seq`/`last_ack_seq` is exactly
+//! the kind of lock-free protocol that's easy to get subtly wrong under
+//! weak memory ordering. Under `--cfg loom`, `AtomicU32`/`AtomicU64` below
+//! resolve to `loom::sync::atomic` instead of `core::sync::atomic`, so
+//! `cargo test` with that flag set model-checks the real production
+//! struct rather than a hand-rolled copy of its protocol. loom has no
+//! `no_std` support, so the crate drops `#![no_std]` for that build, and
+//! `MorpheusScb::new` can no longer be `const fn` (loom's atomic
+//! constructors aren't const) - see the two `cfg`-gated definitions below.
+//! Run with: `RUSTFLAGS="--cfg loom" cargo test --release loom_`.
 
-#![no_std]
+#![cfg_attr(not(loom), no_std)]
 
+#[cfg(not(loom))]
 use core::sync::atomic::{AtomicU32, AtomicU64};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU32, AtomicU64};
 
 /// Shared Control Block (SCB) - One per worker thread
 ///
@@ -64,11 +80,28 @@ pub struct MorpheusScb {
     /// Advisory priority (0-1000).
     pub runtime_priority: AtomicU32,
 
+    /// Requested uninterrupted timeslice in nanoseconds, 0 if none.
+    ///
+    /// Set by `critical_block!`'s `with_timeslice(..)` form so the kernel
+    /// can size cgroup throttling instead of blindly trusting
+    /// `is_in_critical_section`.
+    pub requested_timeslice_ns: AtomicU64,
+
+    /// Bitmask of logical CPUs (bit N = CPU N, up to 64) this worker is
+    /// pinned to, or 0 if unpinned. Set by the runtime after
+    /// `sched_setaffinity` so the BPF side's `select_cpu`/`set_cpumask`
+    /// path can honor the same placement instead of re-deriving it.
+    pub assigned_cpu_mask: AtomicU64,
+
     _pad1: u32,
-    _reserved1: [u64; 3],
+    _reserved1: [u64; 1],
 }
 
-// Compile-time size assertion
+// Compile-time size assertion. Loom's atomics carry extra bookkeeping for
+// its model checker and are not the same size as `core::sync::atomic`'s, so
+// this only holds - and only needs to hold - for the real, non-loom build
+// that actually crosses the kernel/userspace boundary.
+#[cfg(not(loom))]
 const _: () = assert!(
     core::mem::size_of::<MorpheusScb>() == 128,
     "MorpheusScb must be exactly 128 bytes"
@@ -81,6 +114,7 @@ impl MorpheusScb {
     /// * `escapable` - Whether this worker allows forced escalation.
     ///   - Rust workers: typically `true`
     ///   - Python workers: typically `false` (GIL safety)
+    #[cfg(not(loom))]
     pub const fn new(escapable: bool) -> Self {
         Self {
             preempt_seq: AtomicU64::new(0),
@@ -92,8 +126,31 @@ impl MorpheusScb {
             escapable: AtomicU32::new(if escapable { 1 } else { 0 }),
             last_ack_seq: AtomicU64::new(0),
             runtime_priority: AtomicU32::new(500), // Default mid-priority
+            requested_timeslice_ns: AtomicU64::new(0),
+            assigned_cpu_mask: AtomicU64::new(0),
+            _pad1: 0,
+            _reserved1: [0; 1],
+        }
+    }
+
+    /// Same as above, but not `const`: loom's atomic constructors aren't
+    /// `const fn`, so the `--cfg loom` build needs a plain `fn` here.
+    #[cfg(loom)]
+    pub fn new(escapable: bool) -> Self {
+        Self {
+            preempt_seq: AtomicU64::new(0),
+            budget_remaining_ns: AtomicU64::new(0),
+            kernel_pressure_level: AtomicU32::new(0),
+            _pad0: 0,
+            _reserved0: [0; 4],
+            is_in_critical_section: AtomicU32::new(0),
+            escapable: AtomicU32::new(if escapable { 1 } else { 0 }),
+            last_ack_seq: AtomicU64::new(0),
+            runtime_priority: AtomicU32::new(500), // Default mid-priority
+            requested_timeslice_ns: AtomicU64::new(0),
+            assigned_cpu_mask: AtomicU64::new(0),
             _pad1: 0,
-            _reserved1: [0; 3],
+            _reserved1: [0; 1],
         }
     }
 }
@@ -152,6 +209,77 @@ pub struct MorpheusHint {
     pub deadline_ns: u64,
 }
 
+/// Aggregated scheduler statistics.
+///
+/// One copy of this struct lives in each CPU's slot of `stats_map`, which is a
+/// `BPF_MAP_TYPE_PERCPU_ARRAY`. A PERCPU map keeps an independent copy per CPU, so
+/// userspace must sum the fields across every CPU's buffer rather than trusting a
+/// single slot.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MorpheusStats {
+    /// Total scheduler ticks observed
+    pub ticks: u64,
+    /// Total yield hints emitted
+    pub hints: u64,
+    /// Hints dropped due to ring buffer overflow
+    pub dropped: u64,
+    /// Forced escalations performed
+    pub escalations: u64,
+    /// Tasks blocked pending a safe point
+    pub blocked: u64,
+}
+
+impl MorpheusStats {
+    /// Elementwise-add another CPU's stats into this one.
+    pub fn accumulate(&mut self, other: &MorpheusStats) {
+        self.ticks += other.ticks;
+        self.hints += other.hints;
+        self.dropped += other.dropped;
+        self.escalations += other.escalations;
+        self.blocked += other.blocked;
+    }
+}
+
+/// Live-tunable scheduler configuration.
+///
+/// A single instance lives in slot 0 of `config_map` (`BPF_MAP_TYPE_ARRAY`).
+/// The BPF program re-reads it every tick, so userspace can retune the
+/// schedule without reloading the program.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MorpheusConfig {
+    /// Time slice in nanoseconds
+    pub slice_ns: u64,
+    /// Grace period before escalation, in nanoseconds
+    pub grace_period_ns: u64,
+    /// 1 if debug logging is enabled, 0 otherwise
+    pub debug_mode: u32,
+    _pad: u32,
+}
+
+impl MorpheusConfig {
+    /// Build a config from millisecond CLI-style inputs.
+    pub fn new(slice_ms: u64, grace_ms: u64, debug_mode: bool) -> Self {
+        Self {
+            slice_ns: slice_ms * 1_000_000,
+            grace_period_ns: grace_ms * 1_000_000,
+            debug_mode: debug_mode as u32,
+            _pad: 0,
+        }
+    }
+}
+
+impl Default for MorpheusConfig {
+    fn default() -> Self {
+        Self::new(
+            config::DEFAULT_SLICE_NS / 1_000_000,
+            config::GRACE_PERIOD_NS / 1_000_000,
+            false,
+        )
+    }
+}
+
 /// Configuration constants
 pub mod config {
     /// Maximum number of workers supported
@@ -172,6 +300,14 @@ pub mod map_names {
     pub const SCB_MAP: &str = "scb_map";
     pub const HINT_RINGBUF: &str = "hint_ringbuf";
     pub const WORKER_TID_MAP: &str = "worker_tid_map";
+    pub const STATS_MAP: &str = "stats_map";
+    pub const CONFIG_MAP: &str = "config_map";
+    /// `cgroup_id -> weight` hash map consumed by `cgroup_init`/
+    /// `cgroup_set_weight` to scale a cgroup's `dsq_vtime` accounting.
+    pub const CGROUP_WEIGHT_MAP: &str = "cgroup_weight_map";
+    /// Per-CPU array of reclaim flags, set by `cpu_release` and cleared by
+    /// `cpu_acquire`, mmap'd by userspace via `ReclaimMap`.
+    pub const CPU_RECLAIM_MAP: &str = "cpu_reclaim_map";
 }
 
 #[cfg(test)]
@@ -179,6 +315,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[cfg(not(loom))]
     fn test_scb_size_and_alignment() {
         assert_eq!(core::mem::size_of::<MorpheusScb>(), 128);
         assert_eq!(core::mem::align_of::<MorpheusScb>(), 64);
@@ -189,4 +326,161 @@ mod tests {
         assert_eq!(HintReason::try_from(1), Ok(HintReason::Budget));
         assert_eq!(HintReason::try_from(5), Err(()));
     }
+
+    #[test]
+    fn test_stats_accumulate() {
+        let mut total = MorpheusStats::default();
+        let per_cpu = MorpheusStats {
+            ticks: 1,
+            hints: 2,
+            dropped: 3,
+            escalations: 4,
+            blocked: 5,
+        };
+
+        total.accumulate(&per_cpu);
+        total.accumulate(&per_cpu);
+
+        assert_eq!(total.ticks, 2);
+        assert_eq!(total.hints, 4);
+        assert_eq!(total.dropped, 6);
+        assert_eq!(total.escalations, 8);
+        assert_eq!(total.blocked, 10);
+    }
+
+    #[test]
+    fn test_config_from_millis() {
+        let cfg = MorpheusConfig::new(5, 100, true);
+        assert_eq!(cfg.slice_ns, 5_000_000);
+        assert_eq!(cfg.grace_period_ns, 100_000_000);
+        assert_eq!(cfg.debug_mode, 1);
+    }
+}
+
+/// Model-checks the `preempt_seq`/`last_ack_seq` handshake: one thread
+/// plays the kernel, bumping `preempt_seq` as yield requests arrive; the
+/// other plays the runtime, reading `preempt_seq` and copying it into
+/// `last_ack_seq` to acknowledge. Run with:
+/// `RUSTFLAGS="--cfg loom" cargo test --release -p morpheus-common loom_`.
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::MorpheusScb;
+    use loom::sync::atomic::Ordering;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    /// The runtime must never acknowledge a `preempt_seq` it hasn't
+    /// actually observed, and a racing writer can never silently lose
+    /// another's successfully-stored ack to a stale overwrite.
+    ///
+    /// Models `ScbHandle::acknowledge()`'s exact protocol (morpheus-runtime
+    /// can't be depended on from here, so the CAS logic is mirrored
+    /// inline) with *two* concurrent acknowledgers instead of one - a
+    /// single acknowledger never actually contends on `last_ack_seq`, so
+    /// it can't tell a correct CAS from a broken one; racing two is what
+    /// exercises the "Uses CAS to handle races" the real function's doc
+    /// comment claims.
+    #[test]
+    fn ack_never_outpaces_or_regresses_under_racing_acknowledgers() {
+        loom::model(|| {
+            let scb = Arc::new(MorpheusScb::new(true));
+
+            let kernel = {
+                let scb = scb.clone();
+                thread::spawn(move || {
+                    scb.preempt_seq.fetch_add(1, Ordering::Release);
+                })
+            };
+
+            // Single-shot CAS attempt, exactly mirroring
+            // ScbHandle::acknowledge(): bail out once already caught up,
+            // otherwise CAS from the last-seen ack up to the observed
+            // target. Returns the target it stored on success.
+            let acknowledge = |scb: Arc<MorpheusScb>| -> Option<u64> {
+                let target = scb.preempt_seq.load(Ordering::Acquire);
+                let current = scb.last_ack_seq.load(Ordering::Relaxed);
+                if target <= current {
+                    return None;
+                }
+                scb.last_ack_seq
+                    .compare_exchange(current, target, Ordering::Release, Ordering::Relaxed)
+                    .ok()
+            };
+
+            let runtime_a = {
+                let scb = scb.clone();
+                thread::spawn(move || acknowledge(scb))
+            };
+            let runtime_b = {
+                let scb = scb.clone();
+                thread::spawn(move || acknowledge(scb))
+            };
+
+            kernel.join().unwrap();
+            let won_a = runtime_a.join().unwrap();
+            let won_b = runtime_b.join().unwrap();
+
+            let acked = scb.last_ack_seq.load(Ordering::Acquire);
+            let final_seq = scb.preempt_seq.load(Ordering::Acquire);
+
+            // Never invents or outpaces a sequence number that was never
+            // actually observed in preempt_seq.
+            assert!(acked <= final_seq);
+
+            // Whichever racer's CAS actually succeeded must still be
+            // reflected once both have joined.
+            for won in [won_a, won_b].into_iter().flatten() {
+                assert!(acked >= won);
+            }
+        });
+    }
+
+    /// A runtime that keeps retrying `acknowledge()` (the way a real
+    /// checkpoint loop does across successive checkpoints) must fully
+    /// catch up to every request the kernel lands, even when a second
+    /// bump races its retry loop rather than happening safely before it.
+    #[test]
+    fn runtime_never_misses_a_landed_request() {
+        loom::model(|| {
+            let scb = Arc::new(MorpheusScb::new(true));
+
+            let kernel = {
+                let scb = scb.clone();
+                thread::spawn(move || {
+                    scb.preempt_seq.fetch_add(1, Ordering::Release);
+                    scb.preempt_seq.fetch_add(1, Ordering::Release);
+                })
+            };
+
+            let runtime = {
+                let scb = scb.clone();
+                thread::spawn(move || loop {
+                    let target = scb.preempt_seq.load(Ordering::Acquire);
+                    let current = scb.last_ack_seq.load(Ordering::Relaxed);
+                    if target <= current {
+                        break;
+                    }
+                    let _ = scb.last_ack_seq.compare_exchange(
+                        current,
+                        target,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                    );
+                })
+            };
+
+            kernel.join().unwrap();
+            runtime.join().unwrap();
+
+            // Mirrors ScbHandle::yield_requested()'s actual formula: once
+            // a fully-retried runtime has caught up, nothing should still
+            // look like a pending, un-acknowledged request.
+            let preempt = scb.preempt_seq.load(Ordering::Acquire);
+            let acked = scb.last_ack_seq.load(Ordering::Relaxed);
+            assert!(
+                preempt <= acked,
+                "a fully-retried runtime must catch up to every request that landed"
+            );
+        });
+    }
 }
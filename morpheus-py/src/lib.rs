@@ -31,6 +31,7 @@
 use morpheus_runtime::{self as rt, critical::in_critical_section};
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use std::sync::atomic::Ordering;
 
 /// Check for pending kernel yield requests.
@@ -223,6 +224,131 @@ fn is_defensive_mode() -> bool {
         .unwrap_or(false)
 }
 
+/// Glue that wires an asyncio event loop's task factory to the SCB.
+///
+/// Driving coroutine steps manually (so a checkpoint can be inserted
+/// between each one) is generator-trampoline plumbing that reads far more
+/// naturally in Python than re-built with pyo3 call chains, so it's kept
+/// as an embedded source string and `exec`'d once per `install_asyncio`
+/// call rather than hand-assembled from pyo3 primitives.
+const ASYNCIO_INTEGRATION_PY: &str = r#"
+import asyncio
+import types
+
+def _make_task_factory(morpheus, min_interval_ns, max_interval_ns):
+    state = {"interval_ns": max_interval_ns}
+
+    def _watch_pressure(loop):
+        pressure = morpheus.pressure_level()
+        if pressure is not None:
+            # Linearly shrink the forced-yield interval as pressure rises,
+            # so a hot loop under a heavily-loaded kernel gets checked more
+            # often even before it sees an explicit yield_requested() hint.
+            span = max_interval_ns - min_interval_ns
+            state["interval_ns"] = max_interval_ns - (span * pressure) // 100
+        loop.call_later(0.05, _watch_pressure, loop)
+
+    loop_started = {"done": False}
+
+    @types.coroutine
+    def _checkpointing_wrapper(coro, loop):
+        if not loop_started["done"]:
+            loop_started["done"] = True
+            loop.call_later(0.05, _watch_pressure, loop)
+
+        it = coro.__await__() if hasattr(coro, "__await__") else coro
+        send_value = None
+        exc = None
+        last_yield_ns = loop.time() * 1_000_000_000
+
+        while True:
+            try:
+                if exc is not None:
+                    future = it.throw(exc)
+                else:
+                    future = it.send(send_value)
+            except StopIteration as stop:
+                return stop.value
+
+            try:
+                send_value = yield future
+                exc = None
+            except BaseException as e:
+                send_value = None
+                exc = e
+
+            if morpheus.is_in_critical_section_py():
+                continue
+
+            now_ns = loop.time() * 1_000_000_000
+            overdue = (now_ns - last_yield_ns) >= state["interval_ns"]
+            if morpheus.yield_requested() or overdue:
+                # A CancelledError (or any other exception) delivered while
+                # suspended in this forced sleep has to reach `it`, not
+                # just propagate out of the wrapper - otherwise the user's
+                # coroutine never gets a chance to run its own except/
+                # finally cleanup. Catch it here and feed it back through
+                # the normal it.throw() path at the top of the loop instead
+                # of re-raising past it.
+                try:
+                    yield from asyncio.sleep(0).__await__()
+                except BaseException as e:
+                    send_value = None
+                    exc = e
+                    continue
+                morpheus.acknowledge_yield()
+                last_yield_ns = loop.time() * 1_000_000_000
+
+    def factory(loop, coro, *, context=None):
+        wrapped = _checkpointing_wrapper(coro, loop)
+        if context is not None:
+            return asyncio.Task(wrapped, loop=loop, context=context)
+        return asyncio.Task(wrapped, loop=loop)
+
+    return factory
+
+def install(morpheus, loop, min_interval_ns, max_interval_ns):
+    loop.set_task_factory(_make_task_factory(morpheus, min_interval_ns, max_interval_ns))
+"#;
+
+/// Install an asyncio task factory that checkpoints between awaited steps.
+///
+/// Every task the loop creates after this call is wrapped so that, after
+/// each `await` resumes, the wrapper checks `yield_requested()` (skipping
+/// the check entirely while `in_critical_section()` is true) and inserts
+/// an `await asyncio.sleep(0)` plus `acknowledge_yield()` when a yield is
+/// due. A `loop.call_later` watcher re-reads `pressure_level()` every
+/// 50 ms and shortens the max gap between forced yields as pressure
+/// rises, so hot loops get checked more eagerly well before the kernel
+/// escalates.
+///
+/// `min_interval_ns`/`max_interval_ns` bound how aggressively pressure can
+/// shrink that gap; reasonable defaults are a few hundred microseconds and
+/// a few milliseconds respectively.
+#[pyfunction]
+#[pyo3(signature = (loop, min_interval_ns=200_000, max_interval_ns=5_000_000))]
+fn install_asyncio(
+    py: Python<'_>,
+    loop: Bound<'_, PyAny>,
+    min_interval_ns: u64,
+    max_interval_ns: u64,
+) -> PyResult<()> {
+    let module = PyModule::from_code_bound(
+        py,
+        ASYNCIO_INTEGRATION_PY,
+        "morpheus_asyncio_integration.py",
+        "morpheus_asyncio_integration",
+    )?;
+    let morpheus_module = py.import_bound("morpheus")?;
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("morpheus", morpheus_module)?;
+    kwargs.set_item("loop", loop)?;
+    kwargs.set_item("min_interval_ns", min_interval_ns)?;
+    kwargs.set_item("max_interval_ns", max_interval_ns)?;
+    module.getattr("install")?.call((), Some(&kwargs))?;
+    Ok(())
+}
+
 /// Morpheus Python module
 #[pymodule]
 fn morpheus(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -239,6 +365,7 @@ fn morpheus(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(exit_critical_section, m)?)?;
     m.add_function(wrap_pyfunction!(get_stats, m)?)?;
     m.add_function(wrap_pyfunction!(is_defensive_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(install_asyncio, m)?)?;
 
     m.add_class::<CriticalSection>()?;
     m.add_class::<Stats>()?;
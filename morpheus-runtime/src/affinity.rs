@@ -0,0 +1,293 @@
+//! CPU affinity and topology-aware worker placement
+//!
+//! Plain config/`WorkerConfig` lets a caller pick a worker count but not
+//! *where* those workers run, which undercuts the SCB/`select_cpu`
+//! cooperation: the kernel scheduler can't correlate a worker TID with a
+//! stable CPU unless something actually pins it there. This module computes
+//! the per-worker CPU placement and applies it via `sched_setaffinity`.
+//!
+//! `numa_node_count`/`worker_node` expose the same node grouping
+//! `Affinity::NumaAware` resolves to, so [`crate::worker::WorkerPool`] can
+//! shard its task injector one-per-node and hand each worker its own
+//! shard (see [`crate::executor`]).
+
+use std::collections::BTreeSet;
+
+/// A set of logical CPUs a worker may run on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuSet(BTreeSet<usize>);
+
+impl CpuSet {
+    /// A set containing a single CPU.
+    pub fn single(cpu: usize) -> Self {
+        Self(BTreeSet::from([cpu]))
+    }
+
+    /// A set built from an arbitrary collection of CPU indices.
+    pub fn new(cpus: impl IntoIterator<Item = usize>) -> Self {
+        Self(cpus.into_iter().collect())
+    }
+
+    /// The CPU indices in this set, in ascending order.
+    pub fn cpus(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// True if this set contains no CPUs.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Pack this set into a bitmask (bit N = CPU N), for publishing through
+    /// the SCB. CPUs at or past bit 64 are silently dropped: the SCB field
+    /// is a single `u64`, and no machine this runtime targets has more than
+    /// 64 logical CPUs per NUMA node.
+    pub fn to_mask(&self) -> u64 {
+        self.0.iter().fold(0u64, |mask, &cpu| {
+            if cpu < 64 {
+                mask | (1u64 << cpu)
+            } else {
+                mask
+            }
+        })
+    }
+}
+
+/// How worker threads are placed onto CPUs.
+#[derive(Debug, Clone, Default)]
+pub enum Affinity {
+    /// No pinning; the OS scheduler decides (default).
+    #[default]
+    Floating,
+    /// Pin worker `i` to logical CPU `i % available_cpus`.
+    Auto,
+    /// Group workers by NUMA node (round-robin across nodes), pinning each
+    /// worker to every CPU in its assigned node.
+    NumaAware,
+    /// Caller-supplied explicit placement, one `CpuSet` per worker index.
+    /// Workers beyond the end of the list are left unpinned.
+    Custom(Vec<CpuSet>),
+}
+
+/// Resolve the `CpuSet` for worker `worker_index` out of `num_workers`,
+/// under the given `affinity` mode. Returns `None` if the worker should be
+/// left unpinned.
+///
+/// `topology_override` replaces `/sys/devices/system/node` detection under
+/// `Affinity::NumaAware` when given a non-empty slice - set via
+/// `Builder::numa_topology`, mainly so tests can exercise NUMA-aware
+/// placement without a real multi-node machine.
+///
+/// This is pure placement logic, kept separate from the actual
+/// `sched_setaffinity` call so it can be tested without a real thread.
+pub fn resolve_affinity(
+    affinity: &Affinity,
+    worker_index: usize,
+    num_workers: usize,
+    topology_override: Option<&[CpuSet]>,
+) -> Option<CpuSet> {
+    match affinity {
+        Affinity::Floating => None,
+        Affinity::Auto => {
+            let available = available_cpus();
+            Some(CpuSet::single(worker_index % available.max(1)))
+        }
+        Affinity::NumaAware => {
+            let nodes = resolve_numa_topology(topology_override);
+            if nodes.is_empty() {
+                return resolve_affinity(&Affinity::Auto, worker_index, num_workers, None);
+            }
+            let node = worker_index % nodes.len();
+            Some(nodes[node].clone())
+        }
+        Affinity::Custom(sets) => sets.get(worker_index).cloned(),
+    }
+}
+
+/// `topology_override` if non-empty, else the real topology read from
+/// `/sys/devices/system/node`.
+fn resolve_numa_topology(topology_override: Option<&[CpuSet]>) -> Vec<CpuSet> {
+    match topology_override {
+        Some(nodes) if !nodes.is_empty() => nodes.to_vec(),
+        _ => numa_topology(),
+    }
+}
+
+/// Number of NUMA nodes an `Affinity::NumaAware` placement would shard
+/// across - `topology_override`'s length if given, else the real
+/// topology's, falling back to a single node if neither yields anything
+/// (mirrors the `Auto` fallback in `resolve_affinity`).
+pub fn numa_node_count(topology_override: Option<&[CpuSet]>) -> usize {
+    resolve_numa_topology(topology_override).len().max(1)
+}
+
+/// Which NUMA node worker `worker_index` belongs to, under the same
+/// round-robin assignment `resolve_affinity`'s `NumaAware` arm uses.
+pub fn worker_node(worker_index: usize, num_nodes: usize) -> usize {
+    worker_index % num_nodes.max(1)
+}
+
+/// Number of logical CPUs available to this process.
+fn available_cpus() -> usize {
+    std::thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1)
+}
+
+/// Parse the NUMA node layout from `/sys/devices/system/node/node*/cpulist`,
+/// one `CpuSet` per node ordered by node ID. Returns an empty `Vec` if the
+/// topology can't be read (non-Linux, containerized/restricted `/sys`, or a
+/// single-node machine with no node directories at all).
+fn numa_topology() -> Vec<CpuSet> {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        return Vec::new();
+    };
+
+    let mut nodes: Vec<(usize, CpuSet)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            let id: usize = name.strip_prefix("node")?.parse().ok()?;
+            let cpulist = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((id, parse_cpulist(cpulist.trim())))
+        })
+        .collect();
+
+    nodes.sort_by_key(|(id, _)| *id);
+    nodes.into_iter().map(|(_, set)| set).collect()
+}
+
+/// Parse a Linux-style cpulist string (e.g. `"0-3,8,10-11"`) into a `CpuSet`.
+fn parse_cpulist(s: &str) -> CpuSet {
+    let mut cpus = BTreeSet::new();
+    for range in s.split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(cpu) = range.parse::<usize>() {
+                    cpus.insert(cpu);
+                }
+            }
+        }
+    }
+    CpuSet(cpus)
+}
+
+/// Pin the calling thread to `cpus` via `sched_setaffinity`. A no-op that
+/// always succeeds on non-Linux targets, since there's no portable
+/// equivalent and the kernel scheduler cooperation this supports is
+/// Morpheus/BPF-specific anyway.
+#[cfg(target_os = "linux")]
+pub fn apply_affinity(cpus: &CpuSet) -> std::io::Result<()> {
+    use std::mem::MaybeUninit;
+
+    if cpus.is_empty() {
+        return Ok(());
+    }
+
+    unsafe {
+        let mut set = MaybeUninit::<libc::cpu_set_t>::zeroed().assume_init();
+        libc::CPU_ZERO(&mut set);
+        for cpu in cpus.cpus() {
+            libc::CPU_SET(cpu, &mut set);
+        }
+
+        let rc = libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set);
+        if rc != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// No-op on non-Linux targets.
+#[cfg(not(target_os = "linux"))]
+pub fn apply_affinity(_cpus: &CpuSet) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_set_to_mask() {
+        let set = CpuSet::new([0, 2, 5]);
+        assert_eq!(set.to_mask(), 0b10_0101);
+    }
+
+    #[test]
+    fn test_cpu_set_to_mask_drops_bits_past_64() {
+        let set = CpuSet::new([0, 64, 100]);
+        assert_eq!(set.to_mask(), 0b1);
+    }
+
+    #[test]
+    fn test_floating_is_unpinned() {
+        assert_eq!(resolve_affinity(&Affinity::Floating, 0, 4, None), None);
+    }
+
+    #[test]
+    fn test_auto_round_robins_across_available_cpus() {
+        let available = available_cpus();
+        let set = resolve_affinity(&Affinity::Auto, available, available, None).unwrap();
+        assert_eq!(set, CpuSet::single(0));
+    }
+
+    #[test]
+    fn test_custom_placement_is_passed_through() {
+        let sets = vec![CpuSet::single(3), CpuSet::single(7)];
+        let affinity = Affinity::Custom(sets.clone());
+        assert_eq!(resolve_affinity(&affinity, 0, 2, None), Some(sets[0].clone()));
+        assert_eq!(resolve_affinity(&affinity, 1, 2, None), Some(sets[1].clone()));
+        assert_eq!(resolve_affinity(&affinity, 2, 2, None), None);
+    }
+
+    #[test]
+    fn test_numa_aware_uses_topology_override() {
+        let nodes = vec![
+            CpuSet::new([0, 1]),
+            CpuSet::new([2, 3]),
+            CpuSet::new([4, 5]),
+        ];
+        assert_eq!(
+            resolve_affinity(&Affinity::NumaAware, 0, 4, Some(&nodes)),
+            Some(nodes[0].clone())
+        );
+        assert_eq!(
+            resolve_affinity(&Affinity::NumaAware, 3, 4, Some(&nodes)),
+            Some(nodes[0].clone())
+        );
+        assert_eq!(
+            resolve_affinity(&Affinity::NumaAware, 4, 4, Some(&nodes)),
+            Some(nodes[1].clone())
+        );
+    }
+
+    #[test]
+    fn test_numa_node_count_uses_topology_override() {
+        let nodes = vec![CpuSet::single(0), CpuSet::single(1), CpuSet::single(2)];
+        assert_eq!(numa_node_count(Some(&nodes)), 3);
+        assert_eq!(numa_node_count(Some(&[])), numa_node_count(None));
+    }
+
+    #[test]
+    fn test_worker_node_round_robins() {
+        assert_eq!(worker_node(0, 3), 0);
+        assert_eq!(worker_node(2, 3), 2);
+        assert_eq!(worker_node(3, 3), 0);
+        assert_eq!(worker_node(5, 0), 0);
+    }
+
+    #[test]
+    fn test_parse_cpulist_ranges_and_singletons() {
+        let set = parse_cpulist("0-3,8,10-11");
+        assert_eq!(set, CpuSet::new([0, 1, 2, 3, 8, 10, 11]));
+    }
+}
@@ -0,0 +1,291 @@
+//! Dynamically-sized pool for blocking/FFI work
+//!
+//! `LocalExecutor::spawn_blocking` offloads a closure here instead of
+//! running it in place, so a long blocking call (FFI, a syscall that can
+//! stall, GIL-held Python work) never pins one of the async workers the
+//! kernel expects to keep polling `preempt_seq`. Threads are spawned on
+//! demand up to `max_threads` and parked on a condvar once idle, exiting
+//! after `keep_alive` with nothing new to do - the same shape as Tokio's
+//! blocking pool.
+//!
+//! Each blocking thread wraps its closure in [`critical_section`], so if
+//! it happens to be one the embedder also registered an SCB for, the
+//! kernel won't try to force-preempt it mid-FFI-call; on a thread with no
+//! registered SCB this is the usual no-op (see [`crate::worker::try_current_scb`]).
+
+use crate::critical_section;
+use parking_lot::{Condvar, Mutex};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::Waker;
+use std::thread;
+use std::time::Duration;
+
+/// Blocking pool tunables.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockingConfig {
+    /// Maximum number of blocking threads alive at once. Once this many are
+    /// busy, further `spawn_blocking` calls queue and wait for one to free
+    /// up rather than spawning unbounded threads.
+    pub max_threads: usize,
+
+    /// How long an idle blocking thread waits for new work before exiting.
+    pub keep_alive: Duration,
+}
+
+impl Default for BlockingConfig {
+    fn default() -> Self {
+        Self {
+            max_threads: 512,
+            keep_alive: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Blocking pool statistics.
+#[derive(Debug, Default)]
+pub struct BlockingPoolStats {
+    /// Total closures submitted via `spawn_blocking`.
+    pub blocking_tasks_spawned: AtomicU64,
+    /// Blocking threads currently alive (running a closure or parked idle).
+    pub blocking_threads_active: AtomicU32,
+}
+
+type BlockingJob = Box<dyn FnOnce() + Send>;
+
+struct PoolState {
+    /// Threads currently alive (running or parked idle).
+    num_threads: usize,
+    /// Threads currently parked waiting for work.
+    idle_threads: usize,
+}
+
+struct Shared {
+    config: BlockingConfig,
+    queue: Mutex<std::collections::VecDeque<BlockingJob>>,
+    state: Mutex<PoolState>,
+    condvar: Condvar,
+    stats: Arc<BlockingPoolStats>,
+}
+
+impl Shared {
+    /// Dispatch `job`: wake an idle thread if one is parked, spawn a new
+    /// thread if the pool has room, or just leave it queued for whichever
+    /// thread finishes its current job first.
+    fn dispatch(self: &Arc<Self>, job: BlockingJob) {
+        self.queue.lock().push_back(job);
+
+        let mut state = self.state.lock();
+        if state.idle_threads > 0 {
+            drop(state);
+            self.condvar.notify_one();
+        } else if state.num_threads < self.config.max_threads {
+            state.num_threads += 1;
+            drop(state);
+            self.spawn_thread();
+        }
+    }
+
+    fn spawn_thread(self: &Arc<Self>) {
+        let shared = self.clone();
+        shared
+            .stats
+            .blocking_threads_active
+            .fetch_add(1, Ordering::Relaxed);
+        thread::Builder::new()
+            .name("morpheus-blocking".to_string())
+            .spawn(move || shared.run())
+            .expect("failed to spawn blocking thread");
+    }
+
+    fn run(self: Arc<Self>) {
+        loop {
+            let job = self.queue.lock().pop_front();
+            match job {
+                Some(job) => job(),
+                None => {
+                    let mut state = self.state.lock();
+                    state.idle_threads += 1;
+                    let timed_out = self
+                        .condvar
+                        .wait_for(&mut state, self.config.keep_alive)
+                        .timed_out();
+                    state.idle_threads -= 1;
+
+                    // Only exit on a timeout, and only if nothing snuck onto
+                    // the queue between the wait expiring and regaining the
+                    // lock - otherwise loop back around and take it.
+                    if timed_out && self.queue.lock().is_empty() {
+                        state.num_threads -= 1;
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.stats
+            .blocking_threads_active
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Dynamically-sized pool of OS threads for blocking work, shared by every
+/// worker's [`LocalExecutor`](crate::executor::LocalExecutor).
+pub struct BlockingPool {
+    shared: Arc<Shared>,
+}
+
+impl BlockingPool {
+    /// Create a new blocking pool. No threads are spawned until the first
+    /// `spawn_blocking` call.
+    pub fn new(config: BlockingConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                queue: Mutex::new(std::collections::VecDeque::new()),
+                state: Mutex::new(PoolState {
+                    num_threads: 0,
+                    idle_threads: 0,
+                }),
+                condvar: Condvar::new(),
+                stats: Arc::new(BlockingPoolStats::default()),
+            }),
+        }
+    }
+
+    /// Run `f` on a blocking pool thread, wrapped in a critical section,
+    /// returning a future that resolves to its result once it completes.
+    pub fn spawn<F, T>(&self, f: F) -> BlockingFuture<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let handle = Arc::new(BlockingHandle::<T> {
+            result: Mutex::new(None),
+            waker: Mutex::new(None),
+            done: std::sync::atomic::AtomicBool::new(false),
+        });
+
+        let completion = handle.clone();
+        let job: BlockingJob = Box::new(move || {
+            let _guard = critical_section();
+            let result = f();
+            drop(_guard);
+
+            *completion.result.lock() = Some(result);
+            completion.done.store(true, Ordering::Release);
+            if let Some(waker) = completion.waker.lock().take() {
+                waker.wake();
+            }
+        });
+
+        self.shared
+            .stats
+            .blocking_tasks_spawned
+            .fetch_add(1, Ordering::Relaxed);
+        self.shared.dispatch(job);
+
+        BlockingFuture { handle }
+    }
+
+    /// Pool-wide statistics.
+    pub fn stats(&self) -> &Arc<BlockingPoolStats> {
+        &self.shared.stats
+    }
+}
+
+struct BlockingHandle<T> {
+    result: Mutex<Option<T>>,
+    waker: Mutex<Option<Waker>>,
+    done: std::sync::atomic::AtomicBool,
+}
+
+/// Future returned by [`BlockingPool::spawn`], resolving once the
+/// offloaded closure completes.
+pub struct BlockingFuture<T> {
+    handle: Arc<BlockingHandle<T>>,
+}
+
+impl<T> std::future::Future for BlockingFuture<T> {
+    type Output = T;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<T> {
+        if self.handle.done.load(Ordering::Acquire) {
+            return std::task::Poll::Ready(
+                self.handle
+                    .result
+                    .lock()
+                    .take()
+                    .expect("BlockingFuture polled again after completion"),
+            );
+        }
+
+        *self.handle.waker.lock() = Some(cx.waker().clone());
+
+        // Re-check after registering the waker to avoid missing a
+        // completion that raced between the load above and this point.
+        if self.handle.done.load(Ordering::Acquire) {
+            std::task::Poll::Ready(
+                self.handle
+                    .result
+                    .lock()
+                    .take()
+                    .expect("BlockingFuture polled again after completion"),
+            )
+        } else {
+            std::task::Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_blocking_runs_and_resolves() {
+        let pool = BlockingPool::new(BlockingConfig::default());
+        let future = pool.spawn(|| 1 + 1);
+        let result = futures_lite::future::block_on(future);
+        assert_eq!(result, 2);
+        assert_eq!(
+            pool.stats().blocking_tasks_spawned.load(Ordering::Relaxed),
+            1
+        );
+    }
+
+    #[test]
+    fn test_spawn_blocking_reuses_idle_thread() {
+        let pool = BlockingPool::new(BlockingConfig::default());
+        futures_lite::future::block_on(pool.spawn(|| ()));
+        // Give the thread a moment to park as idle before the next job.
+        std::thread::sleep(Duration::from_millis(20));
+        futures_lite::future::block_on(pool.spawn(|| ()));
+
+        assert_eq!(pool.shared.state.lock().num_threads, 1);
+    }
+
+    #[test]
+    fn test_max_threads_caps_pool_size() {
+        let pool = BlockingPool::new(BlockingConfig {
+            max_threads: 2,
+            keep_alive: Duration::from_millis(50),
+        });
+
+        let futures: Vec<_> = (0..4)
+            .map(|_| pool.spawn(|| std::thread::sleep(Duration::from_millis(60))))
+            .collect();
+
+        // All four jobs are submitted, but only two threads should have
+        // been spawned to run them while the rest sit queued.
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(pool.shared.state.lock().num_threads, 2);
+
+        for future in futures {
+            futures_lite::future::block_on(future);
+        }
+    }
+}
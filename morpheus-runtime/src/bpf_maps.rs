@@ -2,19 +2,33 @@
 //!
 //! This module provides functions to register worker TIDs with the kernel's
 //! BPF maps, enabling the kernel scheduler to identify Morpheus workers.
-
-use crate::error::{Error, Result};
-use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+//!
+//! By default this talks to the kernel through raw `SYS_bpf` syscalls (see
+//! `syscall_backend` below), which must match kernel struct alignment
+//! exactly. Building with the `aya-backend` feature swaps this for `aya`'s
+//! safe `Map`/`HashMap` APIs instead, removing that fragile manual attribute
+//! surface (at the cost of depending on aya) and opening the door to CO-RE
+//! relocation so one compiled object can run across kernel versions.
+
+use crate::error::Result;
+use std::os::fd::{AsFd, BorrowedFd};
+
+#[cfg(not(feature = "aya-backend"))]
+mod syscall_backend;
+#[cfg(not(feature = "aya-backend"))]
+use syscall_backend::Backend;
+
+#[cfg(feature = "aya-backend")]
+mod aya_backend;
+#[cfg(feature = "aya-backend")]
+use aya_backend::Backend;
 
 /// Handle to BPF maps used for worker registration
 ///
 /// This struct holds file descriptors to the BPF maps exposed by scx_morpheus.
 /// Workers use this to register their TID and access their SCB.
 pub struct BpfMaps {
-    /// worker_tid_map: TID -> worker_id mapping
-    tid_map_fd: OwnedFd,
-    /// scb_map: worker_id -> SCB mapping (mmappable)
-    scb_map_fd: OwnedFd,
+    backend: Backend,
 }
 
 impl BpfMaps {
@@ -25,66 +39,25 @@ impl BpfMaps {
     /// for worker_tid_map and scb_map respectively.
     pub unsafe fn from_raw_fds(tid_map_fd: i32, scb_map_fd: i32) -> Self {
         Self {
-            tid_map_fd: OwnedFd::from_raw_fd(tid_map_fd),
-            scb_map_fd: OwnedFd::from_raw_fd(scb_map_fd),
+            backend: Backend::from_raw_fds(tid_map_fd, scb_map_fd),
         }
     }
 
-    /// Create a new BpfMaps handle by looking up maps by name
-    ///
-    /// This function attempts to find the maps by their pinned paths or
-    /// by iterating through available maps.
-    /// Create a new BpfMaps handle by looking up maps by name
-    ///
-    /// This function attempts to find the maps by their pinned paths or
-    /// by iterating through available maps.
+    /// Create a new BpfMaps handle by looking up maps by their pinned paths.
     pub fn from_pinned_paths(tid_map_path: &str, scb_map_path: &str) -> Result<Self> {
-        let tid_map_fd = Self::bpf_obj_get(tid_map_path).map_err(|e| {
-            Error::BpfMap(format!("failed to open tid_map at {}: {}", tid_map_path, e))
-        })?;
-
-        let scb_map_fd = Self::bpf_obj_get(scb_map_path).map_err(|e| {
-            Error::BpfMap(format!("failed to open scb_map at {}: {}", scb_map_path, e))
-        })?;
-
         Ok(Self {
-            tid_map_fd,
-            scb_map_fd,
+            backend: Backend::from_pinned_paths(tid_map_path, scb_map_path)?,
         })
     }
 
-    fn bpf_obj_get(pathname: &str) -> std::io::Result<OwnedFd> {
-        let c_path = std::ffi::CString::new(pathname)?;
-        let attr = BpfObjGetAttr {
-            pathname: c_path.as_ptr() as u64,
-            bpf_fd: 0,
-            file_flags: 0,
-        };
-
-        let fd = unsafe {
-            libc::syscall(
-                libc::SYS_bpf,
-                7, // BPF_OBJ_GET
-                &attr as *const _ as *const libc::c_void,
-                std::mem::size_of::<BpfObjGetAttr>(),
-            )
-        };
-
-        if fd < 0 {
-            return Err(std::io::Error::last_os_error());
-        }
-
-        unsafe { Ok(OwnedFd::from_raw_fd(fd as i32)) }
-    }
-
     /// Get the SCB map file descriptor (for mmap)
     pub fn scb_map_fd(&self) -> BorrowedFd<'_> {
-        self.scb_map_fd.as_fd()
+        self.backend.scb_map_fd()
     }
 
     /// Get the TID map file descriptor
     pub fn tid_map_fd(&self) -> BorrowedFd<'_> {
-        self.tid_map_fd.as_fd()
+        self.backend.tid_map_fd()
     }
 
     /// Register a worker thread with the kernel
@@ -92,107 +65,47 @@ impl BpfMaps {
     /// This writes the TID -> worker_id mapping to the BPF hash map,
     /// enabling the kernel to identify this thread as a Morpheus worker.
     pub fn register_worker(&self, tid: u32, worker_id: u32) -> Result<()> {
-        let key = tid.to_ne_bytes();
-        let value = worker_id.to_ne_bytes();
-
-        // Use BPF syscall to update the map
-        let ret = unsafe {
-            libc::syscall(
-                libc::SYS_bpf,
-                2, // BPF_MAP_UPDATE_ELEM
-                &BpfMapUpdateAttr {
-                    map_fd: self.tid_map_fd.as_raw_fd() as u32,
-                    _pad0: 0,
-                    key: key.as_ptr() as u64,
-                    value: value.as_ptr() as u64,
-                    flags: 0, // BPF_ANY
-                } as *const _ as *const libc::c_void,
-                std::mem::size_of::<BpfMapUpdateAttr>(),
-            )
-        };
-
-        if ret < 0 {
-            return Err(Error::Registration(format!(
-                "failed to register worker tid={} id={}: {}",
-                tid,
-                worker_id,
-                std::io::Error::last_os_error()
-            )));
-        }
-
-        tracing::debug!("registered worker tid={} -> id={}", tid, worker_id);
-        Ok(())
+        self.backend.register_worker(tid, worker_id)
     }
 
     /// Unregister a worker thread from the kernel
     ///
     /// This removes the TID from the BPF hash map.
     pub fn unregister_worker(&self, tid: u32) -> Result<()> {
-        let key = tid.to_ne_bytes();
-
-        // Use BPF syscall to delete from the map
-        let ret = unsafe {
-            libc::syscall(
-                libc::SYS_bpf,
-                3, // BPF_MAP_DELETE_ELEM
-                &BpfMapDeleteAttr {
-                    map_fd: self.tid_map_fd.as_raw_fd() as u32,
-                    _pad0: 0,
-                    key: key.as_ptr() as u64,
-                } as *const _ as *const libc::c_void,
-                std::mem::size_of::<BpfMapDeleteAttr>(),
-            )
-        };
-
-        if ret < 0 {
-            let err = std::io::Error::last_os_error();
-            // ENOENT is OK - worker might already be removed
-            if err.raw_os_error() != Some(libc::ENOENT) {
-                return Err(Error::Registration(format!(
-                    "failed to unregister worker tid={}: {}",
-                    tid, err
-                )));
-            }
-        }
-
-        tracing::debug!("unregistered worker tid={}", tid);
-        Ok(())
+        self.backend.unregister_worker(tid)
     }
-}
-
-/// BPF_MAP_UPDATE_ELEM attribute structure
-/// Note: The kernel expects specific field alignment
-#[repr(C)]
-struct BpfMapUpdateAttr {
-    map_fd: u32,
-    _pad0: u32, // Padding for 8-byte alignment of key pointer
-    key: u64,
-    value: u64,
-    flags: u64,
-}
 
-#[allow(dead_code)]
-struct BpfMapDeleteAttr {
-    map_fd: u32,
-    _pad0: u32, // Padding for 8-byte alignment
-    key: u64,
-}
+    /// Attach `cgroup_weight_map` from a raw file descriptor, enabling
+    /// `set_cgroup_weight`/`clear_cgroup_weight`. Optional: most runtimes
+    /// don't use cgroup-aware scheduling and never call this.
+    ///
+    /// # Safety
+    /// The caller must ensure the file descriptor is a valid BPF map fd for
+    /// `cgroup_weight_map`.
+    pub unsafe fn attach_cgroup_weight_map_raw_fd(&mut self, fd: i32) {
+        self.backend.attach_cgroup_weight_map_raw_fd(fd);
+    }
 
-#[repr(C)]
-struct BpfObjGetAttr {
-    pathname: u64,
-    bpf_fd: u32,
-    file_flags: u32,
-}
+    /// Attach `cgroup_weight_map` by its pinned path.
+    pub fn attach_cgroup_weight_map_pinned_path(&mut self, path: &str) -> Result<()> {
+        self.backend.attach_cgroup_weight_map_pinned_path(path)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Set the scheduling weight for the cgroup identified by `cgroup_id`
+    /// (its cgroupfs directory inode number, matching what
+    /// `bpf_get_current_cgroup_id` returns in-kernel). `cgroup_init` seeds
+    /// this value and `cgroup_set_weight` re-reads it live to rescale
+    /// `dsq_vtime` accounting for proportional-share fairness across
+    /// cgroups.
+    ///
+    /// Returns an error if `cgroup_weight_map` hasn't been attached.
+    pub fn set_cgroup_weight(&self, cgroup_id: u64, weight: u32) -> Result<()> {
+        self.backend.set_cgroup_weight(cgroup_id, weight)
+    }
 
-    #[test]
-    fn test_bpf_attr_sizes() {
-        // Ensure our attr structs match expected sizes with proper padding
-        assert_eq!(std::mem::size_of::<BpfMapUpdateAttr>(), 32);
-        assert_eq!(std::mem::size_of::<BpfMapDeleteAttr>(), 16);
+    /// Remove a cgroup's weight entry, reverting it to the scheduler's
+    /// default weight.
+    pub fn clear_cgroup_weight(&self, cgroup_id: u64) -> Result<()> {
+        self.backend.clear_cgroup_weight(cgroup_id)
     }
 }
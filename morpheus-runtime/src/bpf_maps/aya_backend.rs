@@ -0,0 +1,156 @@
+//! Pure-Rust backend built on `aya`
+//!
+//! Replaces the hand-rolled `SYS_bpf` attribute structs with `aya`'s safe
+//! `Map`/`HashMap` wrappers. `scb_map` is still opened as a raw fd: the SCB
+//! map is mmap'd directly by `crate::scb`, and aya doesn't expose an mmap
+//! path for `BPF_MAP_TYPE_ARRAY`-style maps today, so there's nothing to
+//! gain by routing it through aya as well.
+
+use crate::error::{Error, Result};
+use aya::maps::{Map, MapData};
+use parking_lot::Mutex;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+
+/// Duplicate `fd` into a freshly-owned `OwnedFd`.
+///
+/// Used whenever the original fd is already owned elsewhere (by an
+/// `aya::maps::MapData`, or by a caller-owned `OwnedFd` we've already
+/// handed off), so wrapping it directly in another `OwnedFd` would give
+/// two owners the same fd and double-close it.
+fn dup_owned(fd: RawFd, what: &str) -> Result<OwnedFd> {
+    let dup = unsafe { libc::dup(fd) };
+    if dup < 0 {
+        return Err(Error::BpfMap(format!(
+            "failed to dup {} fd: {}",
+            what,
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(dup) })
+}
+
+pub struct Backend {
+    // aya's HashMap::insert/remove take &mut self; workers register and
+    // unregister from different threads, so the raw-syscall backend's
+    // &self, fd-based concurrency has to be recovered with a lock here.
+    tid_map: Mutex<aya::maps::HashMap<MapData, u32, u32>>,
+    // Duplicated once at construction time so `tid_map_fd()` can hand out a
+    // `BorrowedFd` without tying its lifetime to a `MutexGuard`.
+    tid_map_fd: OwnedFd,
+    scb_map_fd: OwnedFd,
+    // Attached lazily via `attach_cgroup_weight_map_*`: most runtimes never
+    // use cgroup-aware scheduling, so there's nothing to open by default.
+    cgroup_weight_map: Option<Mutex<aya::maps::HashMap<MapData, u64, u32>>>,
+}
+
+impl Backend {
+    pub unsafe fn from_raw_fds(tid_map_fd: i32, scb_map_fd: i32) -> Self {
+        // `MapData::from_fd` takes ownership of the fd it's given, and so
+        // does the `OwnedFd` below - so `MapData` gets its own duped fd
+        // rather than sharing the one stored in `self.tid_map_fd`.
+        let tid_map_fd = OwnedFd::from_raw_fd(tid_map_fd);
+        let map_data_fd = tid_map_fd
+            .try_clone()
+            .expect("failed to dup tid_map fd")
+            .into_raw_fd();
+        let map_data =
+            MapData::from_fd(map_data_fd).expect("tid_map fd must be a valid BPF map");
+        Self {
+            tid_map: Mutex::new(
+                aya::maps::HashMap::try_from(Map::HashMap(map_data))
+                    .expect("tid_map must be a BPF_MAP_TYPE_HASH"),
+            ),
+            tid_map_fd,
+            scb_map_fd: OwnedFd::from_raw_fd(scb_map_fd),
+            cgroup_weight_map: None,
+        }
+    }
+
+    pub fn from_pinned_paths(tid_map_path: &str, scb_map_path: &str) -> Result<Self> {
+        let map_data = MapData::from_pin(tid_map_path)
+            .map_err(|e| Error::BpfMap(format!("failed to open pinned {}: {}", tid_map_path, e)))?;
+        // `map_data` already owns its fd; dup it rather than wrapping the
+        // same raw fd in our own `OwnedFd`, which would double-close it
+        // once `map_data` is moved into `tid_map` below.
+        let tid_map_fd = dup_owned(map_data.fd().as_raw_fd(), "tid_map")?;
+        let tid_map = aya::maps::HashMap::try_from(Map::HashMap(map_data))
+            .map_err(|e| Error::BpfMap(format!("{} is not a hash map: {}", tid_map_path, e)))?;
+
+        let scb_map_data = MapData::from_pin(scb_map_path)
+            .map_err(|e| Error::BpfMap(format!("failed to open pinned {}: {}", scb_map_path, e)))?;
+        let scb_map_fd = dup_owned(scb_map_data.fd().as_raw_fd(), "scb_map")?;
+
+        Ok(Self {
+            tid_map: Mutex::new(tid_map),
+            tid_map_fd,
+            scb_map_fd,
+            cgroup_weight_map: None,
+        })
+    }
+
+    pub unsafe fn attach_cgroup_weight_map_raw_fd(&mut self, fd: i32) {
+        let map_data =
+            MapData::from_fd(fd).expect("cgroup_weight_map fd must be a valid BPF map");
+        self.cgroup_weight_map = Some(Mutex::new(
+            aya::maps::HashMap::try_from(Map::HashMap(map_data))
+                .expect("cgroup_weight_map must be a BPF_MAP_TYPE_HASH"),
+        ));
+    }
+
+    pub fn attach_cgroup_weight_map_pinned_path(&mut self, path: &str) -> Result<()> {
+        let map_data = MapData::from_pin(path)
+            .map_err(|e| Error::BpfMap(format!("failed to open pinned {}: {}", path, e)))?;
+        let map = aya::maps::HashMap::try_from(Map::HashMap(map_data))
+            .map_err(|e| Error::BpfMap(format!("{} is not a hash map: {}", path, e)))?;
+        self.cgroup_weight_map = Some(Mutex::new(map));
+        Ok(())
+    }
+
+    pub fn scb_map_fd(&self) -> BorrowedFd<'_> {
+        self.scb_map_fd.as_fd()
+    }
+
+    pub fn tid_map_fd(&self) -> BorrowedFd<'_> {
+        self.tid_map_fd.as_fd()
+    }
+
+    pub fn register_worker(&self, tid: u32, worker_id: u32) -> Result<()> {
+        self.tid_map
+            .lock()
+            .insert(tid, worker_id, 0)
+            .map_err(|e| Error::Registration(format!("tid_map insert failed: {}", e)))
+    }
+
+    pub fn unregister_worker(&self, tid: u32) -> Result<()> {
+        match self.tid_map.lock().remove(&tid) {
+            Ok(()) => Ok(()),
+            Err(aya::maps::MapError::KeyNotFound) => Ok(()),
+            Err(e) => Err(Error::Registration(format!("tid_map remove failed: {}", e))),
+        }
+    }
+
+    pub fn set_cgroup_weight(&self, cgroup_id: u64, weight: u32) -> Result<()> {
+        let map = self
+            .cgroup_weight_map
+            .as_ref()
+            .ok_or_else(|| Error::BpfMap("cgroup_weight_map not attached".to_string()))?;
+        map.lock()
+            .insert(cgroup_id, weight, 0)
+            .map_err(|e| Error::Registration(format!("cgroup_weight_map insert failed: {}", e)))
+    }
+
+    pub fn clear_cgroup_weight(&self, cgroup_id: u64) -> Result<()> {
+        let map = self
+            .cgroup_weight_map
+            .as_ref()
+            .ok_or_else(|| Error::BpfMap("cgroup_weight_map not attached".to_string()))?;
+        match map.lock().remove(&cgroup_id) {
+            Ok(()) => Ok(()),
+            Err(aya::maps::MapError::KeyNotFound) => Ok(()),
+            Err(e) => Err(Error::Registration(format!(
+                "cgroup_weight_map remove failed: {}",
+                e
+            ))),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Raw `SYS_bpf` syscall backend
+//!
+//! No `libbpf-rs`/`aya` dependency is pulled in here: this talks to the
+//! kernel directly via `BPF_MAP_UPDATE_ELEM`, `BPF_MAP_DELETE_ELEM`, and
+//! `BPF_OBJ_GET`, using hand-rolled `#[repr(C)]` attribute structs that must
+//! match the kernel's `union bpf_attr` layout exactly.
+
+use crate::error::{Error, Result};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+
+const BPF_MAP_UPDATE_ELEM: libc::c_int = 2;
+const BPF_MAP_DELETE_ELEM: libc::c_int = 3;
+const BPF_OBJ_GET: libc::c_int = 7;
+
+pub struct Backend {
+    tid_map_fd: OwnedFd,
+    scb_map_fd: OwnedFd,
+    cgroup_weight_map_fd: Option<OwnedFd>,
+}
+
+impl Backend {
+    pub unsafe fn from_raw_fds(tid_map_fd: i32, scb_map_fd: i32) -> Self {
+        Self {
+            tid_map_fd: OwnedFd::from_raw_fd(tid_map_fd),
+            scb_map_fd: OwnedFd::from_raw_fd(scb_map_fd),
+            cgroup_weight_map_fd: None,
+        }
+    }
+
+    pub fn from_pinned_paths(tid_map_path: &str, scb_map_path: &str) -> Result<Self> {
+        let tid_map_fd = bpf_obj_get(tid_map_path)?;
+        let scb_map_fd = bpf_obj_get(scb_map_path)?;
+        Ok(Self {
+            tid_map_fd,
+            scb_map_fd,
+            cgroup_weight_map_fd: None,
+        })
+    }
+
+    pub unsafe fn attach_cgroup_weight_map_raw_fd(&mut self, fd: i32) {
+        self.cgroup_weight_map_fd = Some(OwnedFd::from_raw_fd(fd));
+    }
+
+    pub fn attach_cgroup_weight_map_pinned_path(&mut self, path: &str) -> Result<()> {
+        self.cgroup_weight_map_fd = Some(bpf_obj_get(path)?);
+        Ok(())
+    }
+
+    pub fn scb_map_fd(&self) -> BorrowedFd<'_> {
+        self.scb_map_fd.as_fd()
+    }
+
+    pub fn tid_map_fd(&self) -> BorrowedFd<'_> {
+        self.tid_map_fd.as_fd()
+    }
+
+    pub fn set_cgroup_weight(&self, cgroup_id: u64, weight: u32) -> Result<()> {
+        let map_fd = self.cgroup_weight_map_fd()?;
+
+        let attr = BpfMapUpdateAttr {
+            map_fd: map_fd.as_raw_fd() as u32,
+            _pad0: 0,
+            key: &cgroup_id as *const u64 as u64,
+            value: &weight as *const u32 as u64,
+            flags: 0,
+        };
+
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                BPF_MAP_UPDATE_ELEM,
+                &attr as *const _ as usize,
+                std::mem::size_of::<BpfMapUpdateAttr>(),
+            )
+        };
+
+        if ret < 0 {
+            return Err(Error::Registration(format!(
+                "BPF_MAP_UPDATE_ELEM on cgroup_weight_map failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn clear_cgroup_weight(&self, cgroup_id: u64) -> Result<()> {
+        let map_fd = self.cgroup_weight_map_fd()?;
+
+        let attr = BpfMapDeleteAttr {
+            map_fd: map_fd.as_raw_fd() as u32,
+            _pad0: 0,
+            key: &cgroup_id as *const u64 as u64,
+        };
+
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                BPF_MAP_DELETE_ELEM,
+                &attr as *const _ as usize,
+                std::mem::size_of::<BpfMapDeleteAttr>(),
+            )
+        };
+
+        if ret < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENOENT) {
+                return Ok(());
+            }
+            return Err(Error::Registration(format!(
+                "BPF_MAP_DELETE_ELEM on cgroup_weight_map failed: {}",
+                err
+            )));
+        }
+        Ok(())
+    }
+
+    fn cgroup_weight_map_fd(&self) -> Result<BorrowedFd<'_>> {
+        self.cgroup_weight_map_fd.as_ref().map(|fd| fd.as_fd()).ok_or_else(|| {
+            Error::BpfMap("cgroup_weight_map not attached".to_string())
+        })
+    }
+
+    pub fn register_worker(&self, tid: u32, worker_id: u32) -> Result<()> {
+        let attr = BpfMapUpdateAttr {
+            map_fd: self.tid_map_fd.as_raw_fd() as u32,
+            _pad0: 0,
+            key: &tid as *const u32 as u64,
+            value: &worker_id as *const u32 as u64,
+            flags: 0,
+        };
+
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                BPF_MAP_UPDATE_ELEM,
+                &attr as *const _ as usize,
+                std::mem::size_of::<BpfMapUpdateAttr>(),
+            )
+        };
+
+        if ret < 0 {
+            return Err(Error::Registration(format!(
+                "BPF_MAP_UPDATE_ELEM failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn unregister_worker(&self, tid: u32) -> Result<()> {
+        let attr = BpfMapDeleteAttr {
+            map_fd: self.tid_map_fd.as_raw_fd() as u32,
+            _pad0: 0,
+            key: &tid as *const u32 as u64,
+        };
+
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_bpf,
+                BPF_MAP_DELETE_ELEM,
+                &attr as *const _ as usize,
+                std::mem::size_of::<BpfMapDeleteAttr>(),
+            )
+        };
+
+        if ret < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENOENT) {
+                return Ok(());
+            }
+            return Err(Error::Registration(format!(
+                "BPF_MAP_DELETE_ELEM failed: {}",
+                err
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn bpf_obj_get(path: &str) -> Result<OwnedFd> {
+    let c_path = std::ffi::CString::new(path)
+        .map_err(|_| Error::BpfMap(format!("invalid pin path: {}", path)))?;
+
+    let attr = BpfObjGetAttr {
+        pathname: c_path.as_ptr() as u64,
+        bpf_fd: 0,
+        file_flags: 0,
+    };
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_OBJ_GET,
+            &attr as *const _ as usize,
+            std::mem::size_of::<BpfObjGetAttr>(),
+        )
+    };
+
+    if ret < 0 {
+        return Err(Error::BpfMap(format!(
+            "BPF_OBJ_GET({}) failed: {}",
+            path,
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    Ok(unsafe { OwnedFd::from_raw_fd(ret as i32) })
+}
+
+#[repr(C)]
+struct BpfMapUpdateAttr {
+    map_fd: u32,
+    _pad0: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+struct BpfMapDeleteAttr {
+    map_fd: u32,
+    _pad0: u32,
+    key: u64,
+}
+
+#[repr(C)]
+struct BpfObjGetAttr {
+    pathname: u64,
+    bpf_fd: u32,
+    file_flags: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpf_attr_sizes() {
+        assert_eq!(std::mem::size_of::<BpfMapUpdateAttr>(), 32);
+        assert_eq!(std::mem::size_of::<BpfMapDeleteAttr>(), 16);
+    }
+}
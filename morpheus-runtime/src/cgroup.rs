@@ -0,0 +1,106 @@
+//! cgroup-aware weighted scheduling
+//!
+//! The fallback `sched_ext_ops` already declares `cgroup_init`,
+//! `cgroup_move`, `cgroup_prep_move`, `cgroup_cancel_move`, and
+//! `cgroup_set_weight`, but this tree doesn't carry the BPF program source
+//! that implements them (`scx_morpheus/src/bpf/scx_morpheus.bpf.c` is
+//! referenced by its build script but absent here), so the in-kernel half
+//! of proportional-share `dsq_vtime` scaling can't be wired up from this
+//! module. What this module does provide is the userspace side those
+//! callbacks depend on: associating a worker pool with a cgroup path and
+//! weight, and publishing that weight to `cgroup_weight_map` (see
+//! [`crate::bpf_maps`]) so the BPF side can read it once it exists.
+//!
+//! A cgroup's id here is its cgroupfs directory's inode number, which is
+//! exactly what `bpf_get_current_cgroup_id` returns in-kernel for cgroup v2.
+
+use crate::bpf_maps::BpfMaps;
+use crate::error::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// A worker pool's association with a cgroup and its relative CPU share.
+#[derive(Debug, Clone)]
+pub struct CgroupAssignment {
+    /// Path to the cgroup's directory under cgroupfs (e.g.
+    /// `/sys/fs/cgroup/morpheus/workers`).
+    pub path: PathBuf,
+    /// Relative scheduling weight, in the same units as `sched_ext`'s
+    /// `set_weight` (1-10000, default 100).
+    pub weight: u32,
+}
+
+impl CgroupAssignment {
+    /// Associate a worker pool with `path` at the given relative `weight`.
+    pub fn new(path: impl Into<PathBuf>, weight: u32) -> Self {
+        Self {
+            path: path.into(),
+            weight,
+        }
+    }
+
+    /// Resolve this assignment's cgroup id and push its weight into
+    /// `maps.set_cgroup_weight`.
+    pub fn apply(&self, maps: &BpfMaps) -> Result<()> {
+        let id = cgroup_id(&self.path)?;
+        maps.set_cgroup_weight(id, self.weight)
+    }
+}
+
+/// The cgroup id for the cgroupfs directory at `path`: its inode number,
+/// matching what `bpf_get_current_cgroup_id` returns in-kernel for
+/// cgroup v2.
+#[cfg(target_os = "linux")]
+pub fn cgroup_id(path: &Path) -> Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| Error::BpfMap(format!("failed to stat cgroup path {:?}: {}", path, e)))?;
+
+    if !metadata.is_dir() {
+        return Err(Error::BpfMap(format!(
+            "cgroup path {:?} is not a directory",
+            path
+        )));
+    }
+
+    Ok(metadata.ino())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cgroup_id(_path: &Path) -> Result<u64> {
+    Err(Error::NotSupported(
+        "cgroup ids are only available on Linux".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cgroup_assignment_stores_path_and_weight() {
+        let assignment = CgroupAssignment::new("/sys/fs/cgroup/morpheus", 250);
+        assert_eq!(assignment.path, PathBuf::from("/sys/fs/cgroup/morpheus"));
+        assert_eq!(assignment.weight, 250);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cgroup_id_matches_directory_inode() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = std::env::temp_dir();
+        let id = cgroup_id(&dir).unwrap();
+        assert_eq!(id, std::fs::metadata(&dir).unwrap().ino());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cgroup_id_rejects_non_directory() {
+        let file = std::env::temp_dir().join("morpheus_cgroup_id_test_file");
+        std::fs::write(&file, b"x").unwrap();
+        let result = cgroup_id(&file);
+        std::fs::remove_file(&file).ok();
+        assert!(result.is_err());
+    }
+}
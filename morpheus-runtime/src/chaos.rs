@@ -0,0 +1,319 @@
+//! Chaos/deterministic testing mode
+//!
+//! Inspired by Miri's tunable `compare_exchange_weak` failure rate: rather
+//! than hoping a real kernel schedules an adversarial interleaving during a
+//! test run, `Builder::chaos(ChaosConfig)` installs a seeded, reproducible
+//! source of adversarial scheduling events that `checkpoint_sync()` and the
+//! critical-section exit path consult on every call.
+//!
+//! Three kinds of event can be injected, each independently rated:
+//! - A forced yield at a checkpoint that wouldn't otherwise have yielded.
+//! - A simulated escalation attempt, logged along with whether the runtime
+//!   happened to be inside a critical section at the time — the invariant
+//!   a liar-style test checks is that this is never `true`.
+//! - A random delay before the critical-section flag is actually cleared,
+//!   to widen the race window around `exit_critical`.
+//!
+//! Every injected event is appended to a log with a monotonic sequence
+//! number, so a failing schedule can be identified by its seed and replayed
+//! by re-running with the same `ChaosConfig`.
+
+use parking_lot::{Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Tunable injection rates for chaos mode. All rates are probabilities in
+/// `[0.0, 1.0]` checked independently on each relevant call; `0.0` (the
+/// default) disables that kind of injection entirely.
+#[derive(Debug, Clone, Copy)]
+pub struct ChaosConfig {
+    /// Seed for the deterministic PRNG driving injection decisions. Same
+    /// seed plus same config reproduces the same sequence of events.
+    pub seed: u64,
+
+    /// Probability that `checkpoint_sync()`/`checkpoint!` forces a yield
+    /// that the kernel did not actually request.
+    pub forced_yield_rate: f64,
+
+    /// Probability that a checkpoint simulates a forced-escalation attempt
+    /// against the current worker.
+    pub escalation_rate: f64,
+
+    /// Probability that clearing the critical-section flag on exit is
+    /// delayed by a random slice, up to `max_clear_delay`.
+    pub clear_delay_rate: f64,
+
+    /// Upper bound on the delay injected by `clear_delay_rate`.
+    pub max_clear_delay: Duration,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            forced_yield_rate: 0.0,
+            escalation_rate: 0.0,
+            clear_delay_rate: 0.0,
+            max_clear_delay: Duration::from_micros(50),
+        }
+    }
+}
+
+/// A single injected adversarial event, in the order it was injected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChaosEvent {
+    /// Monotonic sequence number, for ordering and replay.
+    pub seq: u64,
+    pub kind: ChaosEventKind,
+}
+
+/// Kind of adversarial event chaos mode can inject.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChaosEventKind {
+    /// A checkpoint yielded even though the kernel did not request it.
+    ForcedYield,
+    /// A forced-escalation attempt was simulated against the current
+    /// worker. `observed_in_critical_section` is the invariant under test:
+    /// it should never be `true` in a correctly-behaving runtime.
+    SimulatedEscalation {
+        observed_in_critical_section: bool,
+    },
+    /// Clearing the critical-section flag was delayed by `delay`.
+    DelayedClear { delay: Duration },
+}
+
+/// Minimal xorshift64 PRNG. Not cryptographic; chosen only so chaos mode
+/// has no dependency beyond the standard library and reproduces bit-for-bit
+/// across runs given the same seed.
+struct Xorshift64 {
+    state: u64,
+}
+
+impl Xorshift64 {
+    fn new(seed: u64) -> Self {
+        // xorshift is undefined for a zero state; substitute a fixed
+        // nonzero constant so seed 0 is still a valid, reproducible seed.
+        Self {
+            state: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Uniform float in `[0.0, 1.0)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+struct ChaosEngine {
+    config: ChaosConfig,
+    rng: Mutex<Xorshift64>,
+    log: Mutex<Vec<ChaosEvent>>,
+    next_seq: AtomicU64,
+}
+
+impl ChaosEngine {
+    fn new(config: ChaosConfig) -> Self {
+        Self {
+            rng: Mutex::new(Xorshift64::new(config.seed)),
+            config,
+            log: Mutex::new(Vec::new()),
+            next_seq: AtomicU64::new(0),
+        }
+    }
+
+    fn roll(&self, rate: f64) -> bool {
+        rate > 0.0 && self.rng.lock().next_f64() < rate
+    }
+
+    fn record(&self, kind: ChaosEventKind) {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.log.lock().push(ChaosEvent { seq, kind });
+    }
+}
+
+static CHAOS: RwLock<Option<Arc<ChaosEngine>>> = RwLock::new(None);
+
+/// Install chaos mode process-wide with the given configuration.
+pub fn install(config: ChaosConfig) {
+    *CHAOS.write() = Some(Arc::new(ChaosEngine::new(config)));
+}
+
+/// Disable chaos mode, discarding its event log.
+pub fn uninstall() {
+    *CHAOS.write() = None;
+}
+
+/// Whether chaos mode is currently installed.
+pub fn is_enabled() -> bool {
+    CHAOS.read().is_some()
+}
+
+/// Snapshot of every event injected since `install()`, in injection order.
+pub fn events() -> Vec<ChaosEvent> {
+    match CHAOS.read().as_ref() {
+        Some(engine) => engine.log.lock().clone(),
+        None => Vec::new(),
+    }
+}
+
+/// Roll for a forced yield. Called from `checkpoint!`/`checkpoint_sync()`.
+pub(crate) fn maybe_force_yield() -> bool {
+    let Some(engine) = CHAOS.read().clone() else {
+        return false;
+    };
+    if engine.roll(engine.config.forced_yield_rate) {
+        engine.record(ChaosEventKind::ForcedYield);
+        true
+    } else {
+        false
+    }
+}
+
+/// Roll for a simulated escalation attempt, logging whether it landed
+/// inside a critical section. Called from `checkpoint!`/`checkpoint_sync()`.
+/// Returns `true` if an escalation was simulated, so the caller can account
+/// for it as a forced preemption.
+pub(crate) fn maybe_simulate_escalation() -> bool {
+    let Some(engine) = CHAOS.read().clone() else {
+        return false;
+    };
+    if engine.roll(engine.config.escalation_rate) {
+        engine.record(ChaosEventKind::SimulatedEscalation {
+            observed_in_critical_section: crate::critical::in_critical_section(),
+        });
+        true
+    } else {
+        false
+    }
+}
+
+/// Roll for a delay before the critical-section flag is cleared. Called
+/// from the critical-section exit path, before the real clear happens.
+pub(crate) fn maybe_delay_before_clear() {
+    let Some(engine) = CHAOS.read().clone() else {
+        return;
+    };
+    if engine.roll(engine.config.clear_delay_rate) {
+        let delay = {
+            let mut rng = engine.rng.lock();
+            let frac = rng.next_f64();
+            Duration::from_nanos((engine.config.max_clear_delay.as_nanos() as f64 * frac) as u64)
+        };
+        engine.record(ChaosEventKind::DelayedClear { delay });
+        std::thread::sleep(delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    // CHAOS is a single process-wide static: every test here installs,
+    // reads, or uninstalls it, so two of these tests running concurrently
+    // (cargo test's default) can have one test's install/uninstall stomp
+    // on another mid-assertion. Serialize them against each other; they
+    // don't need to be serialized against unrelated tests elsewhere.
+
+    #[test]
+    #[serial(chaos)]
+    fn test_disabled_by_default_injects_nothing() {
+        uninstall();
+        assert!(!is_enabled());
+        assert!(!maybe_force_yield());
+        assert!(events().is_empty());
+    }
+
+    #[test]
+    #[serial(chaos)]
+    fn test_zero_rate_never_fires() {
+        install(ChaosConfig {
+            seed: 42,
+            ..ChaosConfig::default()
+        });
+        for _ in 0..256 {
+            assert!(!maybe_force_yield());
+        }
+        assert!(events().is_empty());
+        uninstall();
+    }
+
+    #[test]
+    #[serial(chaos)]
+    fn test_full_rate_always_fires_and_logs() {
+        install(ChaosConfig {
+            seed: 7,
+            forced_yield_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+        assert!(maybe_force_yield());
+        assert!(maybe_force_yield());
+        assert_eq!(events().len(), 2);
+        assert!(events()
+            .iter()
+            .all(|e| e.kind == ChaosEventKind::ForcedYield));
+        uninstall();
+    }
+
+    #[test]
+    #[serial(chaos)]
+    fn test_same_seed_reproduces_same_sequence() {
+        let config = ChaosConfig {
+            seed: 1234,
+            forced_yield_rate: 0.5,
+            ..ChaosConfig::default()
+        };
+
+        install(config);
+        let first: Vec<bool> = (0..64).map(|_| maybe_force_yield()).collect();
+        uninstall();
+
+        install(config);
+        let second: Vec<bool> = (0..64).map(|_| maybe_force_yield()).collect();
+        uninstall();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    #[serial(chaos)]
+    fn test_escalation_records_critical_section_state() {
+        install(ChaosConfig {
+            seed: 9,
+            escalation_rate: 1.0,
+            ..ChaosConfig::default()
+        });
+
+        maybe_simulate_escalation();
+        {
+            let _guard = crate::critical_section();
+            maybe_simulate_escalation();
+        }
+
+        let logged = events();
+        assert_eq!(logged.len(), 2);
+        assert_eq!(
+            logged[0].kind,
+            ChaosEventKind::SimulatedEscalation {
+                observed_in_critical_section: false
+            }
+        );
+        assert_eq!(
+            logged[1].kind,
+            ChaosEventKind::SimulatedEscalation {
+                observed_in_critical_section: true
+            }
+        );
+        uninstall();
+    }
+}
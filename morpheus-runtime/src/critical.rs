@@ -11,9 +11,75 @@
 //! await points (which would defeat the purpose of cooperative scheduling).
 
 use crate::worker;
+use parking_lot::RwLock;
 use std::cell::Cell;
 use std::marker::PhantomData;
 
+/// Dispatches the first-enter/last-exit effect of a critical section.
+///
+/// `critical_section()` itself always tracks nesting depth in
+/// `CRITICAL_DEPTH`; only what happens on the outermost enter and exit is
+/// pluggable. The default backend forwards to the current worker's SCB
+/// (`ScbHandle::enter_critical`/`exit_critical`). Test harnesses can install
+/// a counting or no-op backend, and deployments with a different map layout
+/// or a userspace-only simulation can supply their own, all without
+/// touching the nesting logic here.
+pub trait CriticalBackend: Send + Sync {
+    /// Called when nesting depth goes from 0 to 1.
+    fn enter(&self);
+    /// Called when nesting depth goes from 1 to 0.
+    fn exit(&self);
+}
+
+/// Backend that forwards to `worker::try_current_scb()`, matching the
+/// behavior this module had before backends were pluggable.
+struct ScbBackend;
+
+impl CriticalBackend for ScbBackend {
+    fn enter(&self) {
+        if let Some(scb) = worker::try_current_scb() {
+            scb.enter_critical();
+        }
+    }
+
+    fn exit(&self) {
+        if let Some(scb) = worker::try_current_scb() {
+            scb.exit_critical();
+        }
+    }
+}
+
+static CRITICAL_BACKEND: RwLock<Option<Box<dyn CriticalBackend>>> = RwLock::new(None);
+
+/// Install a process-global critical-section backend.
+///
+/// Replaces whatever backend was previously installed (or the default SCB
+/// backend if none was). Takes effect for the next outermost enter or exit
+/// on any thread: the backend is looked up at dispatch time, so a section
+/// entered under the previous backend has its exit dispatched to the new
+/// one.
+pub fn set_critical_backend(backend: impl CriticalBackend + 'static) {
+    *CRITICAL_BACKEND.write() = Some(Box::new(backend));
+}
+
+fn backend_enter() {
+    match CRITICAL_BACKEND.read().as_deref() {
+        Some(backend) => backend.enter(),
+        None => ScbBackend.enter(),
+    }
+}
+
+fn backend_exit() {
+    // Chaos mode may inject a delay here to widen the race window around
+    // clearing the critical-section flag; no-op when chaos mode is off.
+    crate::chaos::maybe_delay_before_clear();
+
+    match CRITICAL_BACKEND.read().as_deref() {
+        Some(backend) => backend.exit(),
+        None => ScbBackend.exit(),
+    }
+}
+
 /// RAII guard for critical sections
 ///
 /// While this guard exists, the kernel will not escalate on this worker,
@@ -49,6 +115,15 @@ pub struct CriticalGuard {
 thread_local! {
     /// Track critical section nesting depth
     static CRITICAL_DEPTH: Cell<u32> = const { Cell::new(0) };
+    /// Monotonic count of critical sections entered (depth 0->1
+    /// transitions) on this thread. `CriticalGuard` is `!Send`/`!Sync` and
+    /// so can never still be held by the time a single executor poll
+    /// returns - a before/after `depth()` comparison around a poll would
+    /// always read 0/0 even if a critical section ran and fully exited
+    /// partway through. Comparing this counter instead tells the executor
+    /// whether *any* critical section was entered during a span of code,
+    /// not just whether one is active at the end of it.
+    static CRITICAL_ENTRIES: Cell<u64> = const { Cell::new(0) };
 }
 
 /// Enter a critical section
@@ -83,11 +158,10 @@ pub fn critical_section() -> CriticalGuard {
         let current = depth.get();
         depth.set(current + 1);
 
-        // Only set the SCB flag on first entry
+        // Only dispatch to the backend on first entry
         if current == 0 {
-            if let Some(scb) = worker::try_current_scb() {
-                scb.enter_critical();
-            }
+            backend_enter();
+            CRITICAL_ENTRIES.with(|entries| entries.set(entries.get() + 1));
         }
 
         CriticalGuard {
@@ -104,11 +178,9 @@ impl Drop for CriticalGuard {
             debug_assert!(current > 0, "CriticalGuard dropped without matching enter");
             depth.set(current - 1);
 
-            // Only clear the SCB flag on last exit
+            // Only dispatch to the backend on last exit
             if current == 1 {
-                if let Some(scb) = worker::try_current_scb() {
-                    scb.exit_critical();
-                }
+                backend_exit();
             }
         });
     }
@@ -120,10 +192,154 @@ pub fn in_critical_section() -> bool {
     CRITICAL_DEPTH.with(|depth| depth.get() > 0)
 }
 
+/// Current critical section nesting depth, for `critical_block!`'s
+/// debug-time escape check. Not meant to be called directly.
+#[doc(hidden)]
+#[inline]
+pub fn depth() -> u32 {
+    CRITICAL_DEPTH.with(|depth| depth.get())
+}
+
+/// Monotonic count of critical sections entered on this thread so far.
+///
+/// Meant to be sampled before and after some span of code (e.g. one
+/// executor poll): if the count changed, a critical section was entered
+/// somewhere in that span, even though none can still be active by the
+/// end of it (a `CriticalGuard` can't outlive a single poll).
+#[doc(hidden)]
+#[inline]
+pub fn entries() -> u64 {
+    CRITICAL_ENTRIES.with(|entries| entries.get())
+}
+
+/// Request an uninterrupted timeslice on the current worker's SCB, for
+/// `critical_block!`'s `with_timeslice(..)` form. Not meant to be called
+/// directly.
+#[doc(hidden)]
+#[inline]
+pub fn request_timeslice(timeslice: std::time::Duration) {
+    if let Some(scb) = worker::try_current_scb() {
+        scb.request_timeslice_ns(timeslice.as_nanos() as u64);
+    }
+}
+
+/// Run `$body` inside a critical section, catching attempts to escape it early.
+///
+/// The body is wrapped in a closure that is called immediately. This turns a `break`/
+/// `continue` targeting a loop outside the block into a genuine compile
+/// error (closures are a control-flow boundary for labeled loops), and
+/// means a bare `return` only returns from the block rather than silently
+/// exiting the enclosing function out from under the still-held
+/// `CriticalGuard`. A debug-time assertion additionally checks that the
+/// nesting depth on exit matches the depth on entry, as a last line of
+/// defense.
+///
+/// The `with_timeslice(duration)` form additionally writes a requested
+/// uninterrupted timeslice into the SCB on entry, so the kernel can size
+/// cgroup throttling around it instead of trusting `is_in_critical_section`
+/// alone.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use morpheus_runtime::critical_block;
+/// use std::time::Duration;
+///
+/// let sum = critical_block! {{
+///     // FFI calls here are protected from forced preemption
+///     1 + 1
+/// }};
+/// assert_eq!(sum, 2);
+///
+/// critical_block!(with_timeslice(Duration::from_micros(50)) {
+///     // Needs ~50us uninterrupted
+/// });
+/// ```
+#[macro_export]
+macro_rules! critical_block {
+    ($body:block) => {{
+        let _guard = $crate::critical_section();
+        let __depth = $crate::critical::depth();
+        let __result = (|| $body)();
+        debug_assert_eq!(
+            $crate::critical::depth(),
+            __depth,
+            "critical_block! exited at a different nesting depth than it entered"
+        );
+        __result
+    }};
+    (with_timeslice($timeslice:expr) $body:block) => {{
+        let _guard = $crate::critical_section();
+        $crate::critical::request_timeslice($timeslice);
+        let __depth = $crate::critical::depth();
+        let __result = (|| $body)();
+        debug_assert_eq!(
+            $crate::critical::depth(),
+            __depth,
+            "critical_block! exited at a different nesting depth than it entered"
+        );
+        __result
+    }};
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_custom_backend_counts_outermost_enter_exit_only() {
+        struct CountingBackend {
+            enters: std::sync::Arc<std::sync::atomic::AtomicU32>,
+            exits: std::sync::Arc<std::sync::atomic::AtomicU32>,
+        }
+
+        impl CriticalBackend for CountingBackend {
+            fn enter(&self) {
+                self.enters.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+            fn exit(&self) {
+                self.exits.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        let enters = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let exits = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        set_critical_backend(CountingBackend {
+            enters: enters.clone(),
+            exits: exits.clone(),
+        });
+
+        {
+            let _g1 = critical_section();
+            {
+                let _g2 = critical_section();
+            }
+            assert_eq!(enters.load(std::sync::atomic::Ordering::SeqCst), 1);
+            assert_eq!(exits.load(std::sync::atomic::Ordering::SeqCst), 0);
+        }
+
+        assert_eq!(enters.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(exits.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_critical_block_returns_body_value() {
+        let sum = critical_block! {{ 1 + 1 }};
+        assert_eq!(sum, 2);
+        assert!(!in_critical_section());
+    }
+
+    #[test]
+    fn test_critical_block_with_timeslice_runs_in_critical_section() {
+        // No worker thread is registered in this test context, so
+        // request_timeslice() silently no-ops; this just checks the macro
+        // expands and runs without a registered SCB.
+        critical_block!(with_timeslice(std::time::Duration::from_micros(50)) {
+            assert!(in_critical_section());
+        });
+        assert!(!in_critical_section());
+    }
+
     #[test]
     fn test_critical_section_nesting() {
         assert!(!in_critical_section());
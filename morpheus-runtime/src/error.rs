@@ -35,4 +35,25 @@ pub enum Error {
     /// Operation not supported
     #[error("operation not supported: {0}")]
     NotSupported(String),
+
+    /// Setting up or waiting on an isolated worker (fork/pipe/waitpid)
+    /// failed in the parent. A seccomp install failure happens in the child
+    /// and is reported as `IsolatedTaskFailed` with exit code 127.
+    #[error("isolated worker setup failed: {0}")]
+    Isolation(String),
+
+    /// An isolated worker's child process crashed or was killed (e.g. by
+    /// its own seccomp filter) before returning a result
+    #[error("isolated worker failed: signal={signal:?} exit_code={exit_code:?}")]
+    IsolatedTaskFailed {
+        /// Signal that killed the child, if any
+        signal: Option<i32>,
+        /// Exit code the child returned, if it exited normally
+        exit_code: Option<i32>,
+    },
+
+    /// Failed to build an async runtime (e.g. `morpheus_tokio`'s
+    /// `MorpheusTokioBuilder::build`)
+    #[error("failed to build runtime: {0}")]
+    Build(String),
 }
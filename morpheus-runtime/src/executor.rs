@@ -2,18 +2,76 @@
 //!
 //! Each worker runs a local async executor. The executor checks for
 //! kernel yield requests at poll boundaries.
+//!
+//! Stealing (from an injector shard or a sibling's deque) always pulls a
+//! batch via `steal_batch_and_pop` rather than a single task, so a burst of
+//! related work moves together instead of trickling across cores one task
+//! at a time. `spawn_affine` lets a caller tag a task with the worker it'd
+//! prefer to keep running on; whenever a steal lands a batch, it's
+//! re-sorted so tasks tagged for this worker run first.
+//!
+//! The injector itself is sharded one-per-NUMA-node (see
+//! [`crate::affinity`]) under `Affinity::NumaAware`; a worker drains its
+//! own node's shard before reaching across nodes, to keep queue traffic
+//! off the interconnect. Non-NUMA-aware placements get a single shard, so
+//! this is invisible unless a caller opts in.
 
+use crate::blocking::BlockingPool;
 use crate::critical::in_critical_section;
 use crate::ringbuf::DefensiveMode;
+use crate::sleep::IdleSleep;
 use crate::worker;
 use async_task::{Runnable, Task};
 use crossbeam::deque::{Injector, Stealer, Worker as WorkQueue};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::future::Future;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+/// Cooperative poll budget each worker starts with before an always-ready
+/// future (one that is perpetually `Ready` and immediately reschedules
+/// itself) is forced to yield regardless of kernel/defensive-mode state.
+/// Mirrors Tokio's cooperative-scheduling budget.
+const INITIAL_BUDGET: u32 = 128;
+
+thread_local! {
+    /// Remaining cooperative poll budget for this worker thread. Decremented
+    /// once per task poll in `run_job`; replenished once it runs out and
+    /// forces a real yield (see `should_yield`), so a fresh burst of up to
+    /// `INITIAL_BUDGET` polls gets to run before the next forced yield.
+    static POLL_BUDGET: Cell<u32> = const { Cell::new(INITIAL_BUDGET) };
+}
+
+/// Consume one unit of this worker's cooperative poll budget.
+fn consume_budget() {
+    POLL_BUDGET.with(|budget| {
+        let remaining = budget.get();
+        if remaining > 0 {
+            budget.set(remaining - 1);
+        }
+    });
+}
+
+/// Refill this worker's cooperative poll budget back to `INITIAL_BUDGET`.
+fn replenish_budget() {
+    POLL_BUDGET.with(|budget| budget.set(INITIAL_BUDGET));
+}
+
+/// Whether this worker still has cooperative poll budget left. Consulted by
+/// `should_yield` and by `checkpoint_sync` (bypassed entirely while in a
+/// critical section, so FFI/GIL-held work is never interrupted by it).
+pub fn has_budget() -> bool {
+    POLL_BUDGET.with(|budget| budget.get() > 0)
+}
+
+/// Remaining cooperative poll budget for this worker thread, for tests
+/// that need the exact count rather than `has_budget`'s boolean view.
+#[cfg(test)]
+fn remaining_budget() -> u32 {
+    POLL_BUDGET.with(|budget| budget.get())
+}
+
 /// Executor statistics
 #[derive(Debug, Default)]
 pub struct ExecutorStats {
@@ -27,18 +85,65 @@ pub struct ExecutorStats {
     pub defensive_yields: AtomicU64,
     /// Total polls
     pub polls: AtomicU64,
+    /// Tasks picked up via a steal from a sibling's deque. Each steal now
+    /// pulls roughly half the sibling's queue via `steal_batch_and_pop`
+    /// rather than a single task.
+    pub steals: AtomicU64,
+    /// Steals (from the global injector or a sibling) that pulled a batch
+    /// via `steal_batch_and_pop` instead of coming back empty-handed
+    pub steal_batches: AtomicU64,
+    /// Tasks run on the worker they were tagged to prefer via
+    /// `spawn_affine`, whether picked up locally or after a steal
+    pub affinity_hits: AtomicU64,
+    /// Tasks picked up from this worker's own NUMA-node injector shard
+    pub local_injector_pops: AtomicU64,
+    /// Tasks picked up from a different NUMA node's injector shard, after
+    /// this worker's own shard came up empty
+    pub remote_injector_pops: AtomicU64,
+    /// Tasks shed back to this worker's own injector shard after a
+    /// checkpoint hint
+    pub sheds: AtomicU64,
+    /// Times this worker observed its current CPU reclaimed by a higher
+    /// scheduling class (`cpu_release`) since the last `cpu_acquire`
+    pub cpu_reclaims: AtomicU64,
+    /// Times a task was forced to yield because it exhausted its
+    /// cooperative poll budget, not because of kernel or defensive-mode
+    /// pressure (see [`has_budget`])
+    pub budget_yields: AtomicU64,
+}
+
+/// A queued `Runnable` paired with an optional affinity hint: the id of
+/// the worker it would most like to keep running on, set via
+/// [`LocalExecutor::spawn_affine`]. Plain `spawn` leaves this `None`, so
+/// stealing and local ordering behave exactly as before unless a caller
+/// opts in. Affinity is advisory only - nothing stops a tagged task from
+/// running on a different worker if that's what steals it first.
+pub(crate) struct Job {
+    pub(crate) runnable: Runnable,
+    pub(crate) affinity: Option<u32>,
 }
 
 /// Local executor for a single worker thread
 pub struct LocalExecutor {
     /// Local task queue
-    queue: WorkQueue<Runnable>,
-    /// Global injector for cross-thread spawns
-    injector: Arc<Injector<Runnable>>,
+    queue: WorkQueue<Job>,
+    /// Per-NUMA-node injector shards for cross-thread spawns. A
+    /// non-NUMA-aware pool has exactly one shard, so this behaves just
+    /// like a single global injector unless `Affinity::NumaAware` is in
+    /// use.
+    injectors: Vec<Arc<Injector<Job>>>,
+    /// Index into `injectors` for this worker's own NUMA node - drained
+    /// before falling back to the other shards.
+    node: usize,
     /// Stealers from other workers (for work stealing)
-    stealers: Vec<Stealer<Runnable>>,
+    stealers: Vec<Stealer<Job>>,
     /// Defensive mode controller
     defensive: Arc<DefensiveMode>,
+    /// Idle-worker sleep coordinator, shared across the pool
+    idle: Arc<IdleSleep>,
+    /// Blocking-task pool this executor offloads `spawn_blocking` work to,
+    /// shared across the pool
+    blocking: Arc<BlockingPool>,
     /// Statistics
     stats: Arc<ExecutorStats>,
     /// Shutdown flag
@@ -48,86 +153,277 @@ pub struct LocalExecutor {
 impl LocalExecutor {
     /// Create a new local executor
     pub fn new(
-        injector: Arc<Injector<Runnable>>,
-        stealers: Vec<Stealer<Runnable>>,
+        injectors: Vec<Arc<Injector<Job>>>,
+        node: usize,
+        stealers: Vec<Stealer<Job>>,
         defensive: Arc<DefensiveMode>,
+        idle: Arc<IdleSleep>,
+        blocking: Arc<BlockingPool>,
     ) -> Self {
         Self {
             queue: WorkQueue::new_fifo(),
-            injector,
+            injectors,
+            node,
             stealers,
             defensive,
+            idle,
+            blocking,
             stats: Arc::new(ExecutorStats::default()),
             shutdown: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// This worker's own injector shard, drained before any other node's.
+    fn local_injector(&self) -> &Arc<Injector<Job>> {
+        &self.injectors[self.node]
+    }
+
     /// Spawn a task on this executor
     pub fn spawn<F>(&self, future: F) -> Task<F::Output>
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        let _stats = self.stats.clone();
+        self.spawn_with_affinity(future, None)
+    }
+
+    /// Like `spawn`, but tags the task as preferring to keep running on
+    /// `worker_id`. A worker that picks up a batch of stolen work sorts
+    /// tasks tagged for itself to the front, so related work (e.g. a
+    /// pipeline of tasks spawned by the same request) is more likely to
+    /// keep running on the same core instead of scattering across the
+    /// pool and thrashing caches. Purely advisory: if a different worker
+    /// steals it first, it still runs.
+    pub fn spawn_affine<F>(&self, future: F, worker_id: u32) -> Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_with_affinity(future, Some(worker_id))
+    }
+
+    fn spawn_with_affinity<F>(&self, future: F, affinity: Option<u32>) -> Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        // Re-polls (the task's waker firing) always go through this
+        // worker's node-local injector shard rather than back onto this
+        // specific worker's queue: work stealing means the task may by
+        // then be of interest to any worker on the node, and the waker can
+        // fire from a thread that isn't running an executor at all (e.g.
+        // an I/O or timer thread).
+        let injector = self.local_injector().clone();
+        let idle = self.idle.clone();
         let schedule = move |runnable: Runnable| {
-            // Schedule to thread-local queue if on worker, else to injector
-            // For simplicity, always push to the local queue
-            // In a real implementation, this would check the current thread
-            runnable.run();
+            injector.push(Job { runnable, affinity });
+            idle.new_work();
         };
 
         let (runnable, task) = async_task::spawn(future, schedule);
-        self.queue.push(runnable);
+        self.queue.push(Job { runnable, affinity });
         self.stats.tasks_spawned.fetch_add(1, Ordering::Relaxed);
+        self.idle.new_work();
         task
     }
 
+    /// Run a blocking closure on the shared [`BlockingPool`] instead of this
+    /// worker, so a long FFI/syscall-bound call doesn't pin a worker the
+    /// kernel expects to stay responsive to `preempt_seq`. The returned
+    /// `Task` resolves once the closure completes; awaiting it behaves like
+    /// any other spawned task (same injector-backed re-poll path on wake).
+    pub fn spawn_blocking<F, T>(&self, f: F) -> Task<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn(self.blocking.spawn(f))
+    }
+
     /// Run the executor until shutdown
+    ///
+    /// Busy-polls the local queue, injector, and sibling stealers for up to
+    /// `idle.spin_rounds()` rounds; if nothing turns up and no new work has
+    /// been announced since, parks on `idle` instead of spinning. Any
+    /// `spawn`/injector push/hint arrival wakes parked workers back up.
     pub fn run(&self) {
+        let mut round = 0u32;
+
         while !self.shutdown.load(Ordering::Relaxed) {
-            self.tick();
+            // Latch the JEC before scanning, so work announced while tick()
+            // runs moves it and keeps this worker from parking.
+            let observed_jec = self.idle.jobs_event_counter();
+            if self.tick() {
+                round = 0;
+                continue;
+            }
+
+            if self.idle.no_work_found(round, observed_jec) {
+                round += 1;
+                continue;
+            }
+
+            round = 0;
+            self.idle.mark_inactive();
+            // About to park: charge the time since the last transition to
+            // on-CPU, mirroring the kernel's `stopping` callback.
+            if let Some(scb) = worker::try_current_scb() {
+                scb.mark_stopping();
+            }
+            self.idle.sleep(observed_jec);
+            // Woken back up: charge the parked interval to off-CPU,
+            // mirroring the kernel's `running` callback.
+            if let Some(scb) = worker::try_current_scb() {
+                scb.mark_running();
+            }
+            self.idle.mark_active();
         }
     }
 
     /// Execute one tick of the executor
     pub fn tick(&self) -> bool {
         // Try to get a task from local queue
-        if let Some(runnable) = self.queue.pop() {
-            self.run_task(runnable);
+        if let Some(job) = self.queue.pop() {
+            self.run_job(job);
             return true;
         }
 
-        // Try to steal from global injector
-        if let Some(runnable) = self.injector.steal().success() {
-            self.run_task(runnable);
+        // Try a batched steal from this worker's own NUMA-node injector
+        // shard first: this both picks up a task to run now and refills
+        // the local queue with siblings, so a burst of injected work
+        // doesn't require one injector steal per task, and it keeps queue
+        // traffic off the cross-node interconnect as long as node-local
+        // work is available. Re-sort whatever landed so work tagged for us
+        // runs first.
+        if let Some(job) = self.local_injector().steal_batch_and_pop(&self.queue).success() {
+            self.stats.local_injector_pops.fetch_add(1, Ordering::Relaxed);
+            self.stats.steal_batches.fetch_add(1, Ordering::Relaxed);
+            self.sort_local_queue_affine_first();
+            self.run_job(job);
             return true;
         }
 
-        // Try to steal from other workers
-        for stealer in &self.stealers {
-            if let Some(runnable) = stealer.steal().success() {
-                self.run_task(runnable);
+        // This node's shard is empty: fall back to every other node's
+        // injector before resorting to stealing directly from a sibling's
+        // deque.
+        for offset in 1..self.injectors.len() {
+            let idx = (self.node + offset) % self.injectors.len();
+            if let Some(job) = self.injectors[idx].steal_batch_and_pop(&self.queue).success() {
+                self.stats.remote_injector_pops.fetch_add(1, Ordering::Relaxed);
+                self.stats.steal_batches.fetch_add(1, Ordering::Relaxed);
+                self.sort_local_queue_affine_first();
+                self.run_job(job);
                 return true;
             }
         }
 
+        // Try to steal from other workers, starting at a random sibling so
+        // repeated empty ticks don't hammer the same stealer first every
+        // time. Like the injector above, pull roughly half the victim's
+        // queue rather than a single task, so related work that landed on
+        // one sibling moves together instead of trickling over one at a
+        // time.
+        if !self.stealers.is_empty() {
+            let start = random_index(self.stealers.len());
+            for offset in 0..self.stealers.len() {
+                let idx = (start + offset) % self.stealers.len();
+                if let Some(job) = self.stealers[idx].steal_batch_and_pop(&self.queue).success() {
+                    self.stats.steals.fetch_add(1, Ordering::Relaxed);
+                    self.stats.steal_batches.fetch_add(1, Ordering::Relaxed);
+                    self.sort_local_queue_affine_first();
+                    self.run_job(job);
+                    return true;
+                }
+            }
+        }
+
         false
     }
 
+    /// Drain the local queue and push it back with any job tagged for this
+    /// worker moved to the front, so affine work a steal just pulled in
+    /// runs before the rest of the batch. A no-op off a worker thread
+    /// (nothing to prefer) or when there's nothing to reorder.
+    fn sort_local_queue_affine_first(&self) {
+        let Some(my_id) = worker::current_worker_id() else {
+            return;
+        };
+
+        let mut jobs: Vec<Job> = std::iter::from_fn(|| self.queue.pop()).collect();
+        if jobs.len() > 1 {
+            jobs.sort_by_key(|job| job.affinity != Some(my_id));
+        }
+        for job in jobs {
+            self.queue.push(job);
+        }
+    }
+
     /// Run a single task, checking for yield requests
-    fn run_task(&self, runnable: Runnable) {
+    fn run_job(&self, job: Job) {
         self.stats.polls.fetch_add(1, Ordering::Relaxed);
 
-        // Check for kernel yield before polling
+        // Check for kernel yield, defensive mode, or an exhausted
+        // cooperative poll budget before polling.
         if self.should_yield() {
             // Re-queue the task and yield
-            self.queue.push(runnable);
+            self.queue.push(job);
             self.acknowledge_yield();
             return;
         }
 
+        if job.affinity.is_some() && job.affinity == worker::current_worker_id() {
+            self.stats.affinity_hits.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Critical sections are exempt from the cooperative budget
+        // entirely. `CriticalGuard` is `!Send`/`!Sync` and so can never be
+        // held across an await point, meaning `in_critical_section()` here
+        // (before the poll) only ever reflects depth left over from the
+        // *previous* poll - never whether the task we're about to run is
+        // going to enter one. Compare critical::entries() before and after
+        // the poll instead: that counter moves even though no guard is
+        // still active by the time run() returns, so it actually tells us
+        // whether this poll touched a critical section.
+        let critical_entries_before = crate::critical::entries();
+
         // Run the task
-        runnable.run();
+        job.runnable.run();
+        self.stats.tasks_completed.fetch_add(1, Ordering::Relaxed);
+
+        if crate::critical::entries() == critical_entries_before {
+            consume_budget();
+        }
+
+        // The poll may have observed a kernel yield hint partway through
+        // (checkpoint_sync() inside the task, not the pre-poll check
+        // above), or this CPU may have been reclaimed by a higher
+        // scheduling class mid-poll. Either way, shed the rest of this
+        // worker's local backlog to the injector so sibling cores pick it
+        // up immediately instead of it stalling behind a worker that's
+        // about to yield or no longer owns its core.
+        let reclaimed = crate::reclaim::current_cpu_is_reclaimed();
+        if reclaimed {
+            self.stats.cpu_reclaims.fetch_add(1, Ordering::Relaxed);
+        }
+        if reclaimed || crate::checkpoint_sync() {
+            self.shed_local_queue();
+            std::thread::yield_now();
+        }
+    }
+
+    /// Drain the local queue back onto this worker's own injector shard and
+    /// announce it, so parked siblings wake up to take it.
+    fn shed_local_queue(&self) {
+        while let Some(job) = self.queue.pop() {
+            self.local_injector().push(job);
+            self.stats.sheds.fetch_add(1, Ordering::Relaxed);
+        }
+        self.idle.new_work();
+    }
+
+    /// Number of tasks currently sitting in this worker's local queue.
+    pub fn queue_depth(&self) -> usize {
+        self.queue.len()
     }
 
     /// Check if we should yield before running a task
@@ -151,6 +447,17 @@ impl LocalExecutor {
             }
         }
 
+        // Always-ready futures that keep rescheduling themselves never trip
+        // the checks above, since neither depends on how many times this
+        // worker has polled. Force a yield once the cooperative budget runs
+        // out so they can't starve siblings waiting on the local queue, and
+        // replenish it so the next burst gets a fresh budget.
+        if !has_budget() {
+            self.stats.budget_yields.fetch_add(1, Ordering::Relaxed);
+            replenish_budget();
+            return true;
+        }
+
         false
     }
 
@@ -158,6 +465,7 @@ impl LocalExecutor {
     fn acknowledge_yield(&self) {
         if let Some(scb) = worker::try_current_scb() {
             scb.acknowledge();
+            scb.record_voluntary_yield();
         }
 
         // Brief yield to allow other threads to run
@@ -202,6 +510,15 @@ impl Future for YieldNow {
     }
 }
 
+/// Pick a pseudo-random index in `0..len`, for randomizing steal order.
+/// Uses the OS-seeded hasher std already provides rather than pulling in a
+/// `rand` dependency for what is just a tie-breaker among sibling workers.
+fn random_index(len: usize) -> usize {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    (RandomState::new().build_hasher().finish() as usize) % len
+}
+
 thread_local! {
     /// The current executor for this thread
     static CURRENT_EXECUTOR: RefCell<Option<Arc<LocalExecutor>>> = const { RefCell::new(None) };
@@ -218,3 +535,59 @@ pub(crate) fn set_current_executor(executor: Arc<LocalExecutor>) {
 pub fn current_executor() -> Option<Arc<LocalExecutor>> {
     CURRENT_EXECUTOR.with(|e| e.borrow().clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocking::BlockingConfig;
+    use crate::sleep::SleepConfig;
+
+    fn test_executor() -> Arc<LocalExecutor> {
+        Arc::new(LocalExecutor::new(
+            vec![Arc::new(Injector::new())],
+            0,
+            Vec::new(),
+            Arc::new(DefensiveMode::new(0)),
+            Arc::new(IdleSleep::new(SleepConfig::default())),
+            Arc::new(BlockingPool::new(BlockingConfig::default())),
+        ))
+    }
+
+    fn next_job(executor: &LocalExecutor) -> Job {
+        executor.queue.pop().expect("spawned job should be queued")
+    }
+
+    #[test]
+    fn test_run_job_does_not_consume_budget_for_a_poll_that_entered_a_critical_section() {
+        let executor = test_executor();
+
+        // The only shape a critical section can take around a poll: enter
+        // and exit synchronously, then complete - a CriticalGuard can't
+        // cross an await point, so it can never still be held once this
+        // poll returns.
+        executor.spawn(async {
+            let _guard = crate::critical_section();
+        });
+        let job = next_job(&executor);
+
+        let budget_before = remaining_budget();
+        executor.run_job(job);
+        assert_eq!(
+            remaining_budget(),
+            budget_before,
+            "a poll that entered a critical section should not consume budget"
+        );
+
+        // An ordinary poll right after still consumes budget as normal,
+        // confirming the exemption is specific to the critical-section
+        // poll and not just broken bookkeeping.
+        executor.spawn(async {});
+        let job = next_job(&executor);
+        executor.run_job(job);
+        assert_eq!(
+            remaining_budget(),
+            budget_before - 1,
+            "an ordinary poll should still consume budget"
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Fork + seccomp isolated worker mode for untrusted FFI
+//!
+//! Mirrors the design substrate-validation hosts moved to: rather than
+//! running untrusted FFI in-process on a `WorkerPool` thread, fork a
+//! single-purpose child, drop it into a deny-by-default seccomp-bpf
+//! filter, run the closure, and marshal its result back over a pipe. A
+//! seccomp kill or any other crash is surfaced to the caller as a typed
+//! [`Error::IsolatedTaskFailed`] instead of taking down the worker that
+//! requested the call.
+//!
+//! # Why fork and not a thread
+//!
+//! A seccomp filter installed with `prctl(PR_SET_SECCOMP, ...)` applies to
+//! the calling thread and is inherited by its children, but it cannot be
+//! removed, and a misbehaving filter (or a legitimately crashing task) can
+//! take the whole thread down. Forking gives the isolated call its own
+//! address space and its own fate: killing or crashing the child cannot
+//! corrupt the parent's heap or take other tasks on the same worker with
+//! it.
+//!
+//! # Fork safety
+//!
+//! [`run_isolated`] forks the calling process as-is. If called from a
+//! process with other live threads, only the calling thread survives into
+//! the child; any lock another thread held at fork time (allocator arena
+//! locks in particular) stays held forever in the child's copy. Keep the
+//! closure's own work allocation-light and avoid taking runtime locks
+//! inside it; this is the same constraint any `fork()`-after-threading
+//! caller has to observe, not something specific to Morpheus.
+//!
+//! While a call is in flight, the critical-section semantics are "don't
+//! reap or signal this child" — the parent wraps its blocking wait in
+//! [`critical_section`](crate::critical_section) so the kernel does not
+//! try to force-escalate a worker that is legitimately blocked waiting on
+//! its own child.
+
+use crate::critical_section;
+use crate::error::{Error, Result};
+use std::io::{Read, Write};
+use std::os::fd::{FromRawFd, OwnedFd};
+
+/// AUDIT_ARCH_X86_64, used by the installed filter to refuse to run under
+/// an unexpected syscall ABI (e.g. a 32-bit compat syscall entry).
+const AUDIT_ARCH_X86_64: u32 = 0xC000_003E;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+
+/// Broad categories of syscalls a policy can allow in bulk instead of
+/// listing numbers one at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyscallGroup {
+    /// `read`/`write`/`close`/`lseek` on already-open descriptors.
+    Io,
+    /// `mmap`/`munmap`/`mprotect`/`brk`, needed by any allocator.
+    Memory,
+    /// `rt_sigreturn`/`sigaltstack`, needed to survive signal delivery.
+    Signals,
+    /// `exit`/`exit_group`. Always allowed; every policy needs a way out.
+    Exit,
+}
+
+impl SyscallGroup {
+    fn syscalls(self) -> &'static [i64] {
+        match self {
+            SyscallGroup::Io => &[
+                libc::SYS_read,
+                libc::SYS_write,
+                libc::SYS_close,
+                libc::SYS_lseek,
+            ],
+            SyscallGroup::Memory => &[
+                libc::SYS_mmap,
+                libc::SYS_munmap,
+                libc::SYS_mprotect,
+                libc::SYS_brk,
+            ],
+            SyscallGroup::Signals => &[libc::SYS_rt_sigreturn, libc::SYS_sigaltstack],
+            SyscallGroup::Exit => &[libc::SYS_exit, libc::SYS_exit_group],
+        }
+    }
+}
+
+/// Deny-by-default seccomp-bpf policy for an isolated worker.
+///
+/// `SyscallGroup::Exit` is allowed in every policy by construction; every
+/// other syscall is killed unless explicitly allowed.
+#[derive(Debug, Clone)]
+pub struct SeccompPolicy {
+    allowed: std::collections::BTreeSet<i64>,
+}
+
+impl Default for SeccompPolicy {
+    fn default() -> Self {
+        let mut policy = Self {
+            allowed: std::collections::BTreeSet::new(),
+        };
+        policy.allow_group(SyscallGroup::Exit);
+        policy
+    }
+}
+
+impl SeccompPolicy {
+    /// Start from a deny-by-default policy (only `exit`/`exit_group`
+    /// allowed).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow every syscall in `group`.
+    pub fn allow_group(&mut self, group: SyscallGroup) -> &mut Self {
+        self.allowed.extend(group.syscalls());
+        self
+    }
+
+    /// Allow a single raw syscall number not covered by a [`SyscallGroup`].
+    pub fn allow_syscall(&mut self, nr: i64) -> &mut Self {
+        self.allowed.insert(nr);
+        self
+    }
+
+    /// Build the seccomp-bpf program for this policy and install it on the
+    /// calling thread. Irreversible; only ever called in the forked child,
+    /// immediately before running the untrusted closure.
+    fn install(&self) -> Result<()> {
+        let mut filter = Vec::with_capacity(4 + self.allowed.len() * 2 + 1);
+
+        // Refuse to run under an unexpected syscall ABI.
+        filter.push(libc::sock_filter {
+            code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+            jt: 0,
+            jf: 0,
+            k: 4, // offsetof(seccomp_data, arch)
+        });
+        filter.push(libc::sock_filter {
+            code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+            jt: 1,
+            jf: 0,
+            k: AUDIT_ARCH_X86_64,
+        });
+        filter.push(libc::sock_filter {
+            code: (libc::BPF_RET | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: SECCOMP_RET_KILL_PROCESS,
+        });
+
+        // Load the syscall number and allow it iff it's in `allowed`.
+        filter.push(libc::sock_filter {
+            code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+            jt: 0,
+            jf: 0,
+            k: 0, // offsetof(seccomp_data, nr)
+        });
+        for &nr in &self.allowed {
+            filter.push(libc::sock_filter {
+                code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+                jt: 0,
+                jf: 1,
+                k: nr as u32,
+            });
+            filter.push(libc::sock_filter {
+                code: (libc::BPF_RET | libc::BPF_K) as u16,
+                jt: 0,
+                jf: 0,
+                k: SECCOMP_RET_ALLOW,
+            });
+        }
+        filter.push(libc::sock_filter {
+            code: (libc::BPF_RET | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: SECCOMP_RET_KILL_PROCESS,
+        });
+
+        let prog = libc::sock_fprog {
+            len: filter.len() as u16,
+            filter: filter.as_mut_ptr(),
+        };
+
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(Error::Isolation(format!(
+                    "PR_SET_NO_NEW_PRIVS failed: {}",
+                    std::io::Error::last_os_error()
+                )));
+            }
+            if libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &prog) != 0 {
+                return Err(Error::Isolation(format!(
+                    "PR_SET_SECCOMP failed: {}",
+                    std::io::Error::last_os_error()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+enum ChildStatus {
+    Exited(i32),
+    Signaled(i32),
+}
+
+fn wait_for_child(pid: libc::pid_t) -> Result<ChildStatus> {
+    let mut status: i32 = 0;
+    loop {
+        let ret = unsafe { libc::waitpid(pid, &mut status, 0) };
+        if ret >= 0 {
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(Error::Isolation(format!("waitpid failed: {err}")));
+        }
+    }
+
+    // glibc's WIFEXITED/WIFSIGNALED/etc. are macros, not libc functions;
+    // reimplement the bit tests directly against the raw wait status.
+    if status & 0x7f == 0 {
+        Ok(ChildStatus::Exited((status >> 8) & 0xff))
+    } else {
+        Ok(ChildStatus::Signaled(status & 0x7f))
+    }
+}
+
+fn make_pipe() -> Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0i32; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(Error::Isolation(format!(
+            "pipe failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    unsafe { Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]))) }
+}
+
+/// Write `bytes` to `fd` as a length-prefixed frame. Best-effort: the
+/// child is about to `_exit` regardless of whether the write succeeds.
+fn write_framed(fd: OwnedFd, bytes: &[u8]) {
+    let mut file = std::fs::File::from(fd);
+    let _ = file.write_all(&(bytes.len() as u32).to_le_bytes());
+    let _ = file.write_all(bytes);
+}
+
+/// Read a length-prefixed frame from `fd`. An empty/short read (the child
+/// died before writing anything) is reported as empty output; the actual
+/// failure is surfaced separately via the child's wait status.
+fn read_framed(fd: OwnedFd) -> Vec<u8> {
+    let mut file = std::fs::File::from(fd);
+    let mut len_buf = [0u8; 4];
+    if file.read_exact(&mut len_buf).is_err() {
+        return Vec::new();
+    }
+    let len = u32::from_le_bytes(len_buf) as usize;
+    let mut buf = vec![0u8; len];
+    if file.read_exact(&mut buf).is_err() {
+        return Vec::new();
+    }
+    buf
+}
+
+/// Runs `f` in a forked, seccomp-filtered child process and returns its
+/// result, or a typed [`Error::IsolatedTaskFailed`] if the child crashed
+/// or was killed by its own filter.
+///
+/// See the module docs for the fork-safety caveat: this forks the calling
+/// process as-is, so `f` should avoid work that depends on state held by
+/// sibling threads.
+pub fn run_isolated<F>(policy: &SeccompPolicy, f: F) -> Result<Vec<u8>>
+where
+    F: FnOnce() -> Vec<u8> + std::panic::UnwindSafe,
+{
+    let (read_fd, write_fd) = make_pipe()?;
+
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        return Err(Error::Isolation(format!(
+            "fork failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    if pid == 0 {
+        drop(read_fd);
+        // Past this point we are the child: any failure must terminate
+        // immediately rather than unwind back through code meant for the
+        // parent's process.
+        if policy.install().is_err() {
+            unsafe { libc::_exit(127) };
+        }
+        match std::panic::catch_unwind(f) {
+            Ok(output) => {
+                write_framed(write_fd, &output);
+                unsafe { libc::_exit(0) };
+            }
+            Err(_) => unsafe { libc::_exit(101) },
+        }
+    }
+
+    drop(write_fd);
+    // Don't let the kernel try to force-escalate this worker while it's
+    // legitimately blocked waiting on a child it owns.
+    let _guard = critical_section();
+    let output = read_framed(read_fd);
+    match wait_for_child(pid)? {
+        ChildStatus::Exited(0) => Ok(output),
+        ChildStatus::Exited(code) => Err(Error::IsolatedTaskFailed {
+            signal: None,
+            exit_code: Some(code),
+        }),
+        ChildStatus::Signaled(sig) => Err(Error::IsolatedTaskFailed {
+            signal: Some(sig),
+            exit_code: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_allows_only_exit() {
+        let policy = SeccompPolicy::default();
+        assert!(policy.allowed.contains(&libc::SYS_exit));
+        assert!(policy.allowed.contains(&libc::SYS_exit_group));
+        assert!(!policy.allowed.contains(&libc::SYS_read));
+    }
+
+    #[test]
+    fn test_allow_group_extends_allowed_set() {
+        let mut policy = SeccompPolicy::new();
+        policy.allow_group(SyscallGroup::Io);
+        assert!(policy.allowed.contains(&libc::SYS_read));
+        assert!(policy.allowed.contains(&libc::SYS_write));
+    }
+
+    #[test]
+    fn test_allow_syscall_adds_single_number() {
+        let mut policy = SeccompPolicy::new();
+        policy.allow_syscall(libc::SYS_getpid);
+        assert!(policy.allowed.contains(&libc::SYS_getpid));
+    }
+}
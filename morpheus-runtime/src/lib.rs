@@ -44,18 +44,36 @@
 //! } // Guard dropped, kernel can escalate again
 //! ```
 
+pub mod affinity;
+pub mod blocking;
+pub mod bpf_maps;
+pub mod cgroup;
+pub mod chaos;
 pub mod critical;
 pub mod error;
 pub mod executor;
+pub mod isolate;
+pub mod metrics;
+pub mod reclaim;
 pub mod ringbuf;
 pub mod runtime;
 pub mod scb;
+pub mod sleep;
+pub mod sync;
 pub mod worker;
 
-pub use critical::{critical_section, CriticalGuard};
+pub use affinity::{Affinity, CpuSet};
+pub use blocking::{BlockingConfig, BlockingPool, BlockingPoolStats};
+pub use bpf_maps::BpfMaps;
+pub use cgroup::CgroupAssignment;
+pub use chaos::ChaosConfig;
+pub use critical::{critical_section, set_critical_backend, CriticalBackend, CriticalGuard};
 pub use error::{Error, Result};
+pub use isolate::{SeccompPolicy, SyscallGroup};
+pub use metrics::{metrics, MorpheusMetrics};
+pub use reclaim::ReclaimMap;
 pub use runtime::{Builder, Runtime};
-pub use scb::ScbHandle;
+pub use scb::{ScbHandle, ScbMap, WorkerStats};
 
 /// Re-export common types
 pub use morpheus_common::{HintReason, MorpheusHint, MorpheusScb};
@@ -84,10 +102,8 @@ pub use morpheus_common::{HintReason, MorpheusHint, MorpheusScb};
 #[macro_export]
 macro_rules! checkpoint {
     () => {{
-        if let Some(scb_handle) = $crate::worker::try_current_scb() {
-            if scb_handle.yield_requested() {
-                $crate::executor::yield_now().await;
-            }
+        if $crate::checkpoint_sync() {
+            $crate::executor::yield_now().await;
         }
     }};
 }
@@ -95,12 +111,31 @@ macro_rules! checkpoint {
 /// Synchronous checkpoint for use in non-async contexts.
 ///
 /// Returns `true` if a yield was requested, allowing the caller to decide
-/// how to respond.
+/// how to respond. In addition to the kernel's own request, this is where
+/// chaos mode (see [`chaos`]) rolls for a forced yield and for a simulated
+/// escalation attempt, so both code paths share the exact same checkpoint
+/// behavior whether or not chaos mode is installed.
 #[inline]
 pub fn checkpoint_sync() -> bool {
-    if let Some(scb_handle) = worker::try_current_scb() {
-        scb_handle.yield_requested()
-    } else {
-        false
+    if chaos::maybe_simulate_escalation() {
+        if let Some(scb) = worker::try_current_scb() {
+            scb.record_forced_preempt();
+        }
     }
+
+    let kernel_requested = worker::try_current_scb()
+        .map(|scb_handle| scb_handle.yield_requested())
+        .unwrap_or(false);
+
+    // An exhausted cooperative poll budget (see `executor::has_budget`)
+    // also counts as a yield request, so a tight loop that only calls
+    // `checkpoint!` notices budget exhaustion the same tick the executor
+    // would have forced it between polls. Never while in a critical
+    // section - FFI/GIL-held work must not be interrupted by it.
+    let budget_exhausted = !critical::in_critical_section() && !executor::has_budget();
+
+    kernel_requested
+        || reclaim::current_cpu_is_reclaimed()
+        || chaos::maybe_force_yield()
+        || budget_exhausted
 }
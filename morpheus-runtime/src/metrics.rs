@@ -5,31 +5,212 @@
 //! ## Metrics Exported
 //!
 //! - `morpheus_hint_count_total{worker_id, reason}` - Hints received
-//! - `morpheus_hint_drops_total` - Hints dropped (ring buffer full)  
+//! - `morpheus_hint_drops_total` - Hints dropped (ring buffer full)
 //! - `morpheus_escalation_count_total{policy}` - Escalations performed
 //! - `morpheus_defensive_mode_total{worker_id}` - Defensive mode activations
+//! - `morpheus_priority_donations_total{worker_id}` - Priority ceiling boosts that raised a worker's effective priority
 //! - `morpheus_last_ack_latency_seconds{worker_id}` - Hint acknowledgment latency
+//!
+//! ## Hot-path design
+//!
+//! `record_hint`, `record_defensive_mode`, and `record_ack_latency` are all
+//! keyed by `worker_id`, which is bounded by `config::MAX_WORKERS` - so
+//! instead of a `RwLock<HashMap<..>>` taking a write lock on every call,
+//! each worker gets a pre-sized slot. Hint and defensive-mode counts are
+//! plain `AtomicU64`s; ack-latency is tracked with a constant-memory P²
+//! streaming quantile estimator per tracked quantile (see [`P2Estimator`])
+//! rather than storing raw samples and recomputing buckets on every
+//! scrape - its marker updates aren't independent, so that one slot is a
+//! small per-worker `Mutex`, not bare atomics. `record_escalation` remains
+//! `RwLock<HashMap<..>>`-backed: escalations are rare (not a per-task hot
+//! path) and policy names aren't bounded the way worker IDs are, so
+//! there's no fixed array to shard them into.
 
+use morpheus_common::config::MAX_WORKERS;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+
+/// Hint reasons tracked per-worker. Matches [`morpheus_common::HintReason`]
+/// plus a catch-all for any reason string that doesn't (yet) have a known
+/// variant, so an unrecognized reason is still counted instead of dropped.
+const HINT_REASONS: [&str; 5] = ["budget", "pressure", "imbalance", "deadline", "other"];
+
+fn hint_reason_index(reason: &str) -> usize {
+    HINT_REASONS
+        .iter()
+        .position(|&r| r == reason)
+        .unwrap_or(HINT_REASONS.len() - 1)
+}
+
+/// Quantiles of ack latency tracked per worker via [`P2Estimator`].
+const TRACKED_QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+/// P² (piecewise-parabolic) streaming quantile estimator.
+///
+/// Tracks a single quantile `p` in `O(1)` time and constant memory (five
+/// "markers"), instead of storing raw samples and recomputing fixed
+/// buckets on every scrape. See Jain & Chlamtac, "The P² Algorithm for
+/// Dynamic Calculation of Quantiles and Histograms Without Storing
+/// Observations" (1985).
+struct P2Estimator {
+    p: f64,
+    /// Samples seen so far, used to fill `q`/`n`/`np` from the first 5
+    /// observations; irrelevant once `initialized` is set.
+    init_buffer: Vec<f64>,
+    initialized: bool,
+    /// Marker heights (the 5 tracked quantile estimates, `q[2]` is `p`).
+    q: [f64; 5],
+    /// Actual marker positions.
+    n: [i64; 5],
+    /// Desired (fractional) marker positions.
+    np: [f64; 5],
+    /// Desired-position increments per observation.
+    dn: [f64; 5],
+}
+
+impl P2Estimator {
+    fn new(p: f64) -> Self {
+        Self {
+            p,
+            init_buffer: Vec::with_capacity(5),
+            initialized: false,
+            q: [0.0; 5],
+            n: [0; 5],
+            np: [0.0; 5],
+            dn: [0.0; 5],
+        }
+    }
+
+    fn update(&mut self, x: f64) {
+        if !self.initialized {
+            self.init_buffer.push(x);
+            if self.init_buffer.len() < 5 {
+                return;
+            }
+
+            self.init_buffer.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for i in 0..5 {
+                self.q[i] = self.init_buffer[i];
+                self.n[i] = (i + 1) as i64;
+            }
+            self.np = [
+                1.0,
+                1.0 + 2.0 * self.p,
+                1.0 + 4.0 * self.p,
+                3.0 + 2.0 * self.p,
+                5.0,
+            ];
+            self.dn = [0.0, self.p / 2.0, self.p, (1.0 + self.p) / 2.0, 1.0];
+            self.initialized = true;
+            return;
+        }
+
+        // Find the cell containing x, clamping the extreme markers.
+        let k = if x < self.q[0] {
+            self.q[0] = x;
+            0
+        } else if x >= self.q[4] {
+            self.q[4] = x;
+            3
+        } else {
+            (0..4).find(|&i| self.q[i] <= x && x < self.q[i + 1]).unwrap_or(3)
+        };
+
+        for n in self.n.iter_mut().skip(k + 1) {
+            *n += 1;
+        }
+        for i in 0..5 {
+            self.np[i] += self.dn[i];
+        }
+
+        for i in 1..4 {
+            let d = self.np[i] - self.n[i] as f64;
+            let can_move_right = d >= 1.0 && self.n[i + 1] - self.n[i] > 1;
+            let can_move_left = d <= -1.0 && self.n[i - 1] - self.n[i] < -1;
+            if !can_move_right && !can_move_left {
+                continue;
+            }
+
+            let s = if d >= 0.0 { 1.0 } else { -1.0 };
+            let parabolic = self.parabolic(i, s);
+            self.q[i] = if self.q[i - 1] < parabolic && parabolic < self.q[i + 1] {
+                parabolic
+            } else {
+                self.linear(i, s)
+            };
+            self.n[i] += s as i64;
+        }
+    }
+
+    fn parabolic(&self, i: usize, s: f64) -> f64 {
+        let (q, n) = (&self.q, &self.n);
+        q[i] + s / (n[i + 1] - n[i - 1]) as f64
+            * (((n[i] - n[i - 1]) as f64 + s) * (q[i + 1] - q[i]) / (n[i + 1] - n[i]) as f64
+                + ((n[i + 1] - n[i]) as f64 - s) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]) as f64)
+    }
+
+    fn linear(&self, i: usize, s: f64) -> f64 {
+        let j = (i as i64 + s as i64) as usize;
+        self.q[i] + s * (self.q[j] - self.q[i]) / (self.n[j] - self.n[i]) as f64
+    }
+
+    /// Current estimate of the tracked quantile, or `None` if no samples have
+    /// been recorded. Before the fifth sample it is the nearest-rank
+    /// quantile of the samples buffered so far.
+    fn estimate(&self) -> Option<f64> {
+        if self.initialized {
+            return Some(self.q[2]);
+        }
+        if self.init_buffer.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.init_buffer.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let idx = (((sorted.len() - 1) as f64) * self.p).round() as usize;
+        Some(sorted[idx])
+    }
+}
+
+/// Per-worker counters and latency samples, wait-free to record into except
+/// for the ack-latency P² estimators, which need their marker updates
+/// applied sequentially.
+struct WorkerMetrics {
+    hint_counts: [AtomicU64; HINT_REASONS.len()],
+    defensive_mode_count: AtomicU64,
+    priority_donation_count: AtomicU64,
+    ack_latency_estimators: Mutex<[P2Estimator; TRACKED_QUANTILES.len()]>,
+    ack_latency_sum_ns: AtomicU64,
+    ack_latency_count: AtomicU64,
+}
+
+impl WorkerMetrics {
+    fn new() -> Self {
+        Self {
+            hint_counts: std::array::from_fn(|_| AtomicU64::new(0)),
+            defensive_mode_count: AtomicU64::new(0),
+            priority_donation_count: AtomicU64::new(0),
+            ack_latency_estimators: Mutex::new(std::array::from_fn(|i| {
+                P2Estimator::new(TRACKED_QUANTILES[i])
+            })),
+            ack_latency_sum_ns: AtomicU64::new(0),
+            ack_latency_count: AtomicU64::new(0),
+        }
+    }
+}
 
 /// Metrics collector for Morpheus runtime
 pub struct MorpheusMetrics {
-    /// Total hints received per worker, per reason
-    hint_counts: RwLock<HashMap<(u32, String), AtomicU64>>,
+    /// Per-worker counters and latency samples, indexed by worker ID.
+    workers: Box<[WorkerMetrics]>,
 
     /// Total hints dropped (ring buffer overflow)
     hint_drops: AtomicU64,
 
-    /// Total escalations per policy
+    /// Total escalations per policy. Low-frequency and unbounded key space,
+    /// so this stays lock-based rather than sharded by worker ID.
     escalation_counts: RwLock<HashMap<String, AtomicU64>>,
-
-    /// Defensive mode activations per worker
-    defensive_mode_counts: RwLock<HashMap<u32, AtomicU64>>,
-
-    /// Acknowledgment latency samples per worker (in nanoseconds)
-    ack_latency_samples: RwLock<HashMap<u32, Vec<u64>>>,
 }
 
 impl Default for MorpheusMetrics {
@@ -42,22 +223,21 @@ impl MorpheusMetrics {
     /// Create a new metrics collector
     pub fn new() -> Self {
         Self {
-            hint_counts: RwLock::new(HashMap::new()),
+            workers: (0..MAX_WORKERS).map(|_| WorkerMetrics::new()).collect(),
             hint_drops: AtomicU64::new(0),
             escalation_counts: RwLock::new(HashMap::new()),
-            defensive_mode_counts: RwLock::new(HashMap::new()),
-            ack_latency_samples: RwLock::new(HashMap::new()),
         }
     }
 
+    fn worker(&self, worker_id: u32) -> Option<&WorkerMetrics> {
+        self.workers.get(worker_id as usize)
+    }
+
     /// Record a hint received
     pub fn record_hint(&self, worker_id: u32, reason: &str) {
-        let mut counts = self.hint_counts.write().unwrap();
-        let key = (worker_id, reason.to_string());
-        counts
-            .entry(key)
-            .or_insert_with(|| AtomicU64::new(0))
-            .fetch_add(1, Ordering::Relaxed);
+        if let Some(worker) = self.worker(worker_id) {
+            worker.hint_counts[hint_reason_index(reason)].fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Record a hint drop
@@ -67,6 +247,13 @@ impl MorpheusMetrics {
 
     /// Record an escalation
     pub fn record_escalation(&self, policy: &str) {
+        // Fast path: don't take the write lock if the policy is already
+        // known.
+        if let Some(count) = self.escalation_counts.read().unwrap().get(policy) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
         let mut counts = self.escalation_counts.write().unwrap();
         counts
             .entry(policy.to_string())
@@ -76,23 +263,30 @@ impl MorpheusMetrics {
 
     /// Record defensive mode activation
     pub fn record_defensive_mode(&self, worker_id: u32) {
-        let mut counts = self.defensive_mode_counts.write().unwrap();
-        counts
-            .entry(worker_id)
-            .or_insert_with(|| AtomicU64::new(0))
-            .fetch_add(1, Ordering::Relaxed);
+        if let Some(worker) = self.worker(worker_id) {
+            worker.defensive_mode_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a priority-ceiling boost that actually raised a worker's
+    /// effective priority on entering a critical section.
+    pub fn record_priority_donation(&self, worker_id: u32) {
+        if let Some(worker) = self.worker(worker_id) {
+            worker.priority_donation_count.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Record acknowledgment latency sample
     pub fn record_ack_latency(&self, worker_id: u32, latency_ns: u64) {
-        let mut samples = self.ack_latency_samples.write().unwrap();
-        let worker_samples = samples.entry(worker_id).or_default();
+        if let Some(worker) = self.worker(worker_id) {
+            worker.ack_latency_sum_ns.fetch_add(latency_ns, Ordering::Relaxed);
+            worker.ack_latency_count.fetch_add(1, Ordering::Relaxed);
 
-        // Keep last 1000 samples per worker
-        if worker_samples.len() >= 1000 {
-            worker_samples.remove(0);
+            let mut estimators = worker.ack_latency_estimators.lock().unwrap();
+            for estimator in estimators.iter_mut() {
+                estimator.update(latency_ns as f64);
+            }
         }
-        worker_samples.push(latency_ns);
     }
 
     /// Render metrics in Prometheus text format
@@ -104,14 +298,15 @@ impl MorpheusMetrics {
             "# HELP morpheus_hint_count_total Total hints received by worker and reason\n",
         );
         output.push_str("# TYPE morpheus_hint_count_total counter\n");
-        {
-            let counts = self.hint_counts.read().unwrap();
-            for ((worker_id, reason), count) in counts.iter() {
+        for (worker_id, worker) in self.workers.iter().enumerate() {
+            for (reason, count) in HINT_REASONS.iter().zip(worker.hint_counts.iter()) {
+                let count = count.load(Ordering::Relaxed);
+                if count == 0 {
+                    continue;
+                }
                 output.push_str(&format!(
                     "morpheus_hint_count_total{{worker_id=\"{}\",reason=\"{}\"}} {}\n",
-                    worker_id,
-                    reason,
-                    count.load(Ordering::Relaxed)
+                    worker_id, reason, count
                 ));
             }
         }
@@ -145,66 +340,69 @@ impl MorpheusMetrics {
             "# HELP morpheus_defensive_mode_total Defensive mode activations by worker\n",
         );
         output.push_str("# TYPE morpheus_defensive_mode_total counter\n");
-        {
-            let counts = self.defensive_mode_counts.read().unwrap();
-            for (worker_id, count) in counts.iter() {
-                output.push_str(&format!(
-                    "morpheus_defensive_mode_total{{worker_id=\"{}\"}} {}\n",
-                    worker_id,
-                    count.load(Ordering::Relaxed)
-                ));
+        for (worker_id, worker) in self.workers.iter().enumerate() {
+            let count = worker.defensive_mode_count.load(Ordering::Relaxed);
+            if count == 0 {
+                continue;
             }
+            output.push_str(&format!(
+                "morpheus_defensive_mode_total{{worker_id=\"{}\"}} {}\n",
+                worker_id, count
+            ));
         }
 
-        // Ack latency histogram
+        // Priority donation counts
         output.push_str(
-            "# HELP morpheus_last_ack_latency_seconds Hint acknowledgment latency in seconds\n",
+            "# HELP morpheus_priority_donations_total Priority ceiling boosts that raised a worker's effective priority\n",
         );
-        output.push_str("# TYPE morpheus_last_ack_latency_seconds histogram\n");
-        {
-            let samples = self.ack_latency_samples.read().unwrap();
-            for (worker_id, worker_samples) in samples.iter() {
-                if worker_samples.is_empty() {
-                    continue;
-                }
+        output.push_str("# TYPE morpheus_priority_donations_total counter\n");
+        for (worker_id, worker) in self.workers.iter().enumerate() {
+            let count = worker.priority_donation_count.load(Ordering::Relaxed);
+            if count == 0 {
+                continue;
+            }
+            output.push_str(&format!(
+                "morpheus_priority_donations_total{{worker_id=\"{}\"}} {}\n",
+                worker_id, count
+            ));
+        }
 
-                // Calculate histogram buckets (in seconds)
-                let buckets = [0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01];
-                let mut bucket_counts = vec![0u64; buckets.len()];
-                let mut sum_ns: u64 = 0;
-
-                for &sample_ns in worker_samples.iter() {
-                    sum_ns = sum_ns.saturating_add(sample_ns);
-                    let sample_s = sample_ns as f64 / 1_000_000_000.0;
-                    for (i, &bucket) in buckets.iter().enumerate() {
-                        if sample_s <= bucket {
-                            bucket_counts[i] += 1;
-                        }
-                    }
-                }
+        // Ack latency quantiles, as a Prometheus summary: a constant-memory
+        // P² estimate per tracked quantile instead of fixed histogram
+        // buckets recomputed from up to 1000 raw samples per worker.
+        output.push_str(
+            "# HELP morpheus_last_ack_latency_seconds Hint acknowledgment latency in seconds (P\u{b2} streaming estimate)\n",
+        );
+        output.push_str("# TYPE morpheus_last_ack_latency_seconds summary\n");
+        for (worker_id, worker) in self.workers.iter().enumerate() {
+            let count = worker.ack_latency_count.load(Ordering::Relaxed);
+            if count == 0 {
+                continue;
+            }
 
-                for (i, &bucket) in buckets.iter().enumerate() {
+            let estimators = worker.ack_latency_estimators.lock().unwrap();
+            for (p, estimator) in TRACKED_QUANTILES.iter().zip(estimators.iter()) {
+                if let Some(value_ns) = estimator.estimate() {
                     output.push_str(&format!(
-                        "morpheus_last_ack_latency_seconds_bucket{{worker_id=\"{}\",le=\"{}\"}} {}\n",
-                        worker_id, bucket, bucket_counts[i]
+                        "morpheus_last_ack_latency_seconds{{worker_id=\"{}\",quantile=\"{}\"}} {}\n",
+                        worker_id,
+                        p,
+                        value_ns / 1_000_000_000.0
                     ));
                 }
-                output.push_str(&format!(
-                    "morpheus_last_ack_latency_seconds_bucket{{worker_id=\"{}\",le=\"+Inf\"}} {}\n",
-                    worker_id,
-                    worker_samples.len()
-                ));
-                output.push_str(&format!(
-                    "morpheus_last_ack_latency_seconds_sum{{worker_id=\"{}\"}} {}\n",
-                    worker_id,
-                    sum_ns as f64 / 1_000_000_000.0
-                ));
-                output.push_str(&format!(
-                    "morpheus_last_ack_latency_seconds_count{{worker_id=\"{}\"}} {}\n",
-                    worker_id,
-                    worker_samples.len()
-                ));
             }
+            drop(estimators);
+
+            let sum_ns = worker.ack_latency_sum_ns.load(Ordering::Relaxed);
+            output.push_str(&format!(
+                "morpheus_last_ack_latency_seconds_sum{{worker_id=\"{}\"}} {}\n",
+                worker_id,
+                sum_ns as f64 / 1_000_000_000.0
+            ));
+            output.push_str(&format!(
+                "morpheus_last_ack_latency_seconds_count{{worker_id=\"{}\"}} {}\n",
+                worker_id, count
+            ));
         }
 
         output
@@ -233,7 +431,7 @@ mod tests {
         m.record_hint_drop();
         m.record_escalation("thread_kick");
         m.record_defensive_mode(0);
-        m.record_ack_latency(0, 50_000); // 50Âµs
+        m.record_ack_latency(0, 50_000); // 50us
 
         let output = m.render();
         assert!(output.contains("morpheus_hint_count_total"));
@@ -242,4 +440,75 @@ mod tests {
         assert!(output.contains("morpheus_defensive_mode_total"));
         assert!(output.contains("morpheus_last_ack_latency_seconds"));
     }
+
+    #[test]
+    fn test_hint_counts_are_per_worker_and_per_reason() {
+        let m = MorpheusMetrics::new();
+        m.record_hint(3, "budget");
+        m.record_hint(3, "deadline");
+        m.record_hint(7, "budget");
+
+        let output = m.render();
+        assert!(output.contains("worker_id=\"3\",reason=\"budget\"} 1"));
+        assert!(output.contains("worker_id=\"3\",reason=\"deadline\"} 1"));
+        assert!(output.contains("worker_id=\"7\",reason=\"budget\"} 1"));
+    }
+
+    #[test]
+    fn test_unknown_hint_reason_falls_back_to_other() {
+        let m = MorpheusMetrics::new();
+        m.record_hint(0, "made_up_reason");
+
+        let output = m.render();
+        assert!(output.contains("worker_id=\"0\",reason=\"other\"} 1"));
+    }
+
+    #[test]
+    fn test_out_of_range_worker_id_does_not_panic() {
+        let m = MorpheusMetrics::new();
+        m.record_hint(MAX_WORKERS + 1, "budget");
+        m.record_defensive_mode(MAX_WORKERS + 1);
+        m.record_priority_donation(MAX_WORKERS + 1);
+        m.record_ack_latency(MAX_WORKERS + 1, 1234);
+        // No panic, and nothing to render for a worker that doesn't exist.
+    }
+
+    #[test]
+    fn test_p2_estimator_converges_near_true_median_of_uniform_samples() {
+        let mut estimator = P2Estimator::new(0.5);
+        for i in 1..=1000u64 {
+            estimator.update(i as f64);
+        }
+
+        let median = estimator.estimate().unwrap();
+        assert!(
+            (median - 500.0).abs() < 50.0,
+            "expected estimate near 500, got {median}"
+        );
+    }
+
+    #[test]
+    fn test_p2_estimator_returns_none_only_before_first_sample() {
+        let mut estimator = P2Estimator::new(0.99);
+        assert_eq!(estimator.estimate(), None);
+
+        estimator.update(1.0);
+        estimator.update(2.0);
+        assert!(estimator.estimate().is_some(), "should have a rough estimate from partial data");
+    }
+
+    #[test]
+    fn test_ack_latency_renders_as_summary_with_quantiles() {
+        let m = MorpheusMetrics::new();
+        for i in 1..=20u64 {
+            m.record_ack_latency(0, i * 1_000);
+        }
+
+        let output = m.render();
+        assert!(output.contains("TYPE morpheus_last_ack_latency_seconds summary"));
+        assert!(output.contains("quantile=\"0.5\""));
+        assert!(output.contains("quantile=\"0.99\""));
+        assert!(output.contains("morpheus_last_ack_latency_seconds_sum{worker_id=\"0\"}"));
+        assert!(output.contains("morpheus_last_ack_latency_seconds_count{worker_id=\"0\"} 20"));
+    }
 }
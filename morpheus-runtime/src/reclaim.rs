@@ -0,0 +1,152 @@
+//! Per-CPU reclaim tracking for `cpu_release`/`cpu_acquire` cooperation
+//!
+//! `sched_ext` fires `cpu_release` when a higher scheduling class (RT,
+//! deadline, stop task) preempts an SCX-controlled CPU, and `cpu_acquire`
+//! when it's handed back. Without reacting to these, a worker keeps
+//! enqueuing onto a CPU it no longer owns, spiking tail latency. The BPF
+//! side of this (flipping a flag in `cpu_reclaim_map` from the `cpu_release`/
+//! `cpu_acquire` callbacks) lives in the BPF program source, which this tree
+//! doesn't carry (`scx_morpheus/src/bpf/scx_morpheus.bpf.c` is referenced by
+//! its build script but absent here) — see [`crate::cgroup`] for the same
+//! caveat. This module is the userspace side: a mmap'd view of
+//! `cpu_reclaim_map` that the worker run loop consults at
+//! [`crate::checkpoint_sync`] time, so a worker whose CPU was reclaimed
+//! sheds its local queue instead of continuing to dispatch onto a contended
+//! core.
+
+use crate::error::{Error, Result};
+use parking_lot::RwLock;
+use std::fs::File;
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::os::unix::io::FromRawFd;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Handle to a memory-mapped `cpu_reclaim_map`: one `AtomicU32` flag per CPU,
+/// non-zero while that CPU has been reclaimed by a higher scheduling class.
+pub struct ReclaimMap {
+    ptr: NonNull<AtomicU32>,
+    num_cpus: usize,
+    _mmap: memmap2::MmapMut,
+}
+
+// SAFETY: access is through atomics only.
+unsafe impl Send for ReclaimMap {}
+unsafe impl Sync for ReclaimMap {}
+
+impl ReclaimMap {
+    /// Map `num_cpus` flags out of the `cpu_reclaim_map` BPF map.
+    ///
+    /// # Safety
+    /// The caller must ensure `map_fd` is a valid fd for `cpu_reclaim_map`
+    /// sized for at least `num_cpus` entries.
+    pub unsafe fn new(map_fd: BorrowedFd<'_>, num_cpus: usize) -> Result<Self> {
+        let len = num_cpus * std::mem::size_of::<AtomicU32>();
+
+        let dup_fd = libc::dup(map_fd.as_raw_fd());
+        if dup_fd < 0 {
+            return Err(Error::Mmap(std::io::Error::last_os_error()));
+        }
+        let file = File::from_raw_fd(dup_fd);
+
+        let mmap = memmap2::MmapOptions::new()
+            .len(len)
+            .map_mut(&file)
+            .map_err(Error::Mmap)?;
+        std::mem::forget(file);
+
+        let ptr = NonNull::new(mmap.as_ptr() as *mut AtomicU32)
+            .ok_or_else(|| Error::Mmap(std::io::Error::other("mmap returned null")))?;
+
+        Ok(Self {
+            ptr,
+            num_cpus,
+            _mmap: mmap,
+        })
+    }
+
+    fn flag(&self, cpu: usize) -> Option<&AtomicU32> {
+        if cpu >= self.num_cpus {
+            return None;
+        }
+        // SAFETY: bounds-checked above; the pointer is valid for the
+        // lifetime of this handle.
+        Some(unsafe { &*self.ptr.as_ptr().add(cpu) })
+    }
+
+    /// True if `cpu` is currently reclaimed by a higher scheduling class.
+    pub fn is_reclaimed(&self, cpu: usize) -> bool {
+        self.flag(cpu)
+            .map(|flag| flag.load(Ordering::Acquire) != 0)
+            .unwrap_or(false)
+    }
+
+    /// Mark `cpu` reclaimed from userspace. Nothing in this tree calls it;
+    /// it sets the same flag the kernel side sets on `cpu_release`.
+    pub fn mark_reclaimed(&self, cpu: usize) {
+        if let Some(flag) = self.flag(cpu) {
+            flag.store(1, Ordering::Release);
+        }
+    }
+
+    /// Clear `cpu`'s reclaimed flag, as `cpu_acquire` should on hand-back.
+    pub fn mark_acquired(&self, cpu: usize) {
+        if let Some(flag) = self.flag(cpu) {
+            flag.store(0, Ordering::Release);
+        }
+    }
+}
+
+/// Process-global installed reclaim map, mirroring the install/uninstall
+/// pattern used by [`crate::chaos`]'s `CHAOS` global.
+static RECLAIM: RwLock<Option<Arc<ReclaimMap>>> = RwLock::new(None);
+
+/// Install a mapped `cpu_reclaim_map` for the whole process to consult.
+pub fn install(map: ReclaimMap) {
+    *RECLAIM.write() = Some(Arc::new(map));
+}
+
+/// Remove the installed reclaim map, if any.
+pub fn uninstall() {
+    *RECLAIM.write() = None;
+}
+
+/// True if the CPU this thread is currently running on has been reclaimed.
+/// `false` if no reclaim map is installed, or on non-Linux targets where
+/// there's no portable way to ask which CPU is current.
+pub(crate) fn current_cpu_is_reclaimed() -> bool {
+    let Some(map) = RECLAIM.read().clone() else {
+        return false;
+    };
+    match current_cpu() {
+        Some(cpu) => map.is_reclaimed(cpu),
+        None => false,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn current_cpu() -> Option<usize> {
+    let cpu = unsafe { libc::sched_getcpu() };
+    if cpu < 0 {
+        None
+    } else {
+        Some(cpu as usize)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_cpu() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_cpu_is_reclaimed_false_without_install() {
+        uninstall();
+        assert!(!current_cpu_is_reclaimed());
+    }
+}
@@ -2,10 +2,26 @@
 //!
 //! Consumes yield hints from the kernel via the BPF ring buffer.
 //! Detects overflow conditions and triggers defensive mode.
+//!
+//! ## Loom model checking
+//!
+//! `DefensiveMode::should_yield` racing against `enter`/`exit` is a
+//! classic place to get the `fetch_sub` underflow boundary wrong. Under
+//! `--cfg loom`, the atomics below resolve to `loom::sync::atomic` so
+//! `cargo test` model-checks the real `DefensiveMode`, not a copy of its
+//! protocol - see the `loom_tests` module at the bottom of this file. Run
+//! with: `RUSTFLAGS="--cfg loom" cargo test --release loom_`.
 
+use crate::sleep::IdleSleep;
 use morpheus_common::{HintReason, MorpheusHint};
+#[cfg(not(loom))]
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(not(loom))]
 use std::sync::Arc;
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(loom)]
+use loom::sync::Arc;
 use tracing::{debug, warn};
 
 /// Statistics for ring buffer consumption
@@ -30,6 +46,11 @@ pub struct HintConsumer {
     defensive_mode: Arc<AtomicBool>,
     /// Statistics
     stats: Arc<RingBufStats>,
+    /// Woken on every hint, if set, so idle siblings notice a hint arrived
+    /// (often a sign the targeted worker is about to yield and free up
+    /// work or CPU) instead of waiting out the rest of their spin/sleep
+    /// cycle.
+    idle: Option<Arc<IdleSleep>>,
 }
 
 impl HintConsumer {
@@ -39,6 +60,16 @@ impl HintConsumer {
             last_seq: AtomicU64::new(0),
             defensive_mode: Arc::new(AtomicBool::new(false)),
             stats: Arc::new(RingBufStats::default()),
+            idle: None,
+        }
+    }
+
+    /// Create a hint consumer that also wakes idle workers via `idle`
+    /// whenever a hint arrives.
+    pub fn with_idle_sleep(idle: Arc<IdleSleep>) -> Self {
+        Self {
+            idle: Some(idle),
+            ..Self::new()
         }
     }
 
@@ -71,6 +102,13 @@ impl HintConsumer {
 
         self.stats.hints_received.fetch_add(1, Ordering::Relaxed);
 
+        // A hint often means its target worker is about to yield, freeing
+        // up work or CPU - wake idle siblings so they notice right away
+        // instead of waiting out the rest of their spin/sleep cycle.
+        if let Some(idle) = &self.idle {
+            idle.new_work();
+        }
+
         // Check for sequence gaps (indicates dropped hints)
         let last = self.last_seq.load(Ordering::Relaxed);
         if hint.seq > last + 1 && last > 0 {
@@ -183,3 +221,71 @@ impl Default for DefensiveMode {
         Self::new(100) // Default: 100 forced yields
     }
 }
+
+/// Model-checks `DefensiveMode::should_yield` racing against itself and
+/// against `enter`/`exit`. Run with:
+/// `RUSTFLAGS="--cfg loom" cargo test --release loom_`.
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::thread;
+
+    /// Several threads race `should_yield` against each other once the
+    /// budget is down to its last unit. `should_yield` only ever reads
+    /// `active` to decide whether to decrement at all, so every racer that
+    /// observes it `true` before the exhausting `fetch_sub` lands also
+    /// gets a `true` back - the budget is advisory, not a hard cap, by
+    /// design. What must hold regardless of interleaving is that
+    /// `active` is cleared by the time every racer has returned, so the
+    /// next checkpoint correctly sees defensive mode as over.
+    #[test]
+    fn concurrent_should_yield_clears_active_once_budget_exhausted() {
+        loom::model(|| {
+            let mode = Arc::new(DefensiveMode::new(1));
+            mode.enter();
+
+            let racers: Vec<_> = (0..2)
+                .map(|_| {
+                    let mode = mode.clone();
+                    thread::spawn(move || mode.should_yield())
+                })
+                .collect();
+
+            for racer in racers {
+                racer.join().unwrap();
+            }
+
+            assert!(!mode.is_active());
+        });
+    }
+
+    /// `should_yield` racing against a concurrent `exit` must never panic
+    /// or corrupt state enough to make `is_active()` itself blow up -
+    /// `fetch_sub` wrapping past zero when `exit` has already zeroed the
+    /// counter is expected (the next `enter` overwrites it unconditionally)
+    /// and must stay contained to `yields_remaining`, never leaving
+    /// `active` readable as anything but a valid `bool`.
+    #[test]
+    fn should_yield_racing_exit_never_panics() {
+        loom::model(|| {
+            let mode = Arc::new(DefensiveMode::new(1));
+            mode.enter();
+
+            let yielder = {
+                let mode = mode.clone();
+                thread::spawn(move || mode.should_yield())
+            };
+            let exiter = {
+                let mode = mode.clone();
+                thread::spawn(move || mode.exit())
+            };
+
+            yielder.join().unwrap();
+            exiter.join().unwrap();
+
+            // Just reading it back must never panic, whichever thread's
+            // writes landed last.
+            let _ = mode.is_active();
+        });
+    }
+}
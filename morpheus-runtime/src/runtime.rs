@@ -2,8 +2,17 @@
 //!
 //! The Runtime coordinates workers, SCBs, and executors.
 
+use crate::affinity::{Affinity, CpuSet};
+use crate::blocking::{BlockingConfig, BlockingPool};
+use crate::bpf_maps::BpfMaps;
+use crate::cgroup::CgroupAssignment;
+use crate::chaos::{self, ChaosConfig};
+use crate::error::Result;
+use crate::executor::Job;
+use crate::isolate::{self, SeccompPolicy};
 use crate::ringbuf::{DefensiveMode, HintConsumer};
-use crate::worker::{WorkerConfig, WorkerPool};
+use crate::sleep::{IdleSleep, SleepConfig};
+use crate::worker::{BroadcastContext, BroadcastFuture, WorkerConfig, WorkerPool};
 use crossbeam::deque::Injector;
 use parking_lot::RwLock;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -22,6 +31,20 @@ pub struct RuntimeConfig {
 
     /// Ring buffer poll timeout
     pub poll_timeout: Duration,
+
+    /// Idle-worker sleep tunables (spin rounds, wake batch size)
+    pub sleep: SleepConfig,
+
+    /// Blocking-task pool tunables (max threads, keep-alive timeout)
+    pub blocking: BlockingConfig,
+
+    /// Chaos/deterministic testing mode, disabled unless set via
+    /// `Builder::chaos`.
+    pub chaos: Option<ChaosConfig>,
+
+    /// cgroup this runtime's worker pool should be scheduled under, with
+    /// its relative CPU share, if set via `Builder::cgroup`.
+    pub cgroup: Option<CgroupAssignment>,
 }
 
 impl Default for RuntimeConfig {
@@ -30,6 +53,10 @@ impl Default for RuntimeConfig {
             workers: WorkerConfig::default(),
             defensive_yields: 100,
             poll_timeout: Duration::from_millis(1),
+            sleep: SleepConfig::default(),
+            blocking: BlockingConfig::default(),
+            chaos: None,
+            cgroup: None,
         }
     }
 }
@@ -62,6 +89,22 @@ impl Builder {
         self
     }
 
+    /// Set how worker threads are pinned to CPUs
+    pub fn cpu_affinity(mut self, affinity: Affinity) -> Self {
+        self.config.workers.cpu_affinity = affinity;
+        self
+    }
+
+    /// Override NUMA node detection with an explicit layout, one `CpuSet`
+    /// per node, bypassing `/sys/devices/system/node`. Only consulted under
+    /// `Affinity::NumaAware`; mainly useful for exercising per-node
+    /// injector sharding in tests on a machine with no real multi-node
+    /// topology to read.
+    pub fn numa_topology(mut self, nodes: Vec<CpuSet>) -> Self {
+        self.config.workers.numa_topology = Some(nodes);
+        self
+    }
+
     /// Set defensive mode yield count
     pub fn defensive_yields(mut self, count: u64) -> Self {
         self.config.defensive_yields = count;
@@ -74,6 +117,49 @@ impl Builder {
         self
     }
 
+    /// Set the number of spin rounds an idle worker attempts before parking
+    pub fn spin_rounds(mut self, rounds: u32) -> Self {
+        self.config.sleep.spin_rounds = rounds;
+        self
+    }
+
+    /// Set how many sleeping workers a single wakeup wakes at most
+    pub fn wake_batch_size(mut self, size: u32) -> Self {
+        self.config.sleep.wake_batch_size = size;
+        self
+    }
+
+    /// Set the maximum number of blocking-pool threads alive at once
+    pub fn max_blocking_threads(mut self, max: usize) -> Self {
+        self.config.blocking.max_threads = max;
+        self
+    }
+
+    /// Set how long an idle blocking-pool thread waits for new work before
+    /// exiting
+    pub fn blocking_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.config.blocking.keep_alive = keep_alive;
+        self
+    }
+
+    /// Associate this runtime's worker pool with a cgroup path and relative
+    /// CPU share, for proportional-share fairness against other cgroups
+    /// (other Morpheus pools, or unrelated processes). Call
+    /// `Runtime::apply_cgroup_weight` once the BPF maps are connected to
+    /// actually publish it.
+    pub fn cgroup(mut self, assignment: CgroupAssignment) -> Self {
+        self.config.cgroup = Some(assignment);
+        self
+    }
+
+    /// Enable chaos mode: `checkpoint_sync()` and the critical-section exit
+    /// path will probabilistically inject adversarial scheduling events
+    /// according to `config`, seeded for reproducible replay.
+    pub fn chaos(mut self, config: ChaosConfig) -> Self {
+        self.config.chaos = Some(config);
+        self
+    }
+
     /// Build the runtime
     ///
     /// Note: This does not connect to the kernel scheduler. Call
@@ -101,7 +187,7 @@ pub struct Runtime {
     workers: RwLock<Option<WorkerPool>>,
 
     /// Global task injector
-    injector: Arc<Injector<async_task::Runnable>>,
+    injector: Arc<Injector<Job>>,
 
     /// Defensive mode controller
     defensive: Arc<DefensiveMode>,
@@ -109,6 +195,12 @@ pub struct Runtime {
     /// Hint consumer
     hints: Arc<HintConsumer>,
 
+    /// Idle-worker sleep coordinator, shared by every worker's executor
+    idle: Arc<IdleSleep>,
+
+    /// Blocking-task pool, shared by every worker's executor
+    blocking: Arc<BlockingPool>,
+
     /// Running flag
     running: AtomicBool,
 }
@@ -116,12 +208,20 @@ pub struct Runtime {
 impl Runtime {
     /// Create a new runtime with the given configuration
     fn new(config: RuntimeConfig) -> Self {
+        if let Some(chaos_config) = config.chaos {
+            chaos::install(chaos_config);
+        }
+
+        let idle = Arc::new(IdleSleep::new(config.sleep));
+
         Self {
             defensive: Arc::new(DefensiveMode::new(config.defensive_yields)),
+            hints: Arc::new(HintConsumer::with_idle_sleep(idle.clone())),
+            idle,
+            blocking: Arc::new(BlockingPool::new(config.blocking)),
             config,
             workers: RwLock::new(None),
             injector: Arc::new(Injector::new()),
-            hints: Arc::new(HintConsumer::new()),
             running: AtomicBool::new(false),
         }
     }
@@ -146,6 +246,17 @@ impl Runtime {
         &self.defensive
     }
 
+    /// Get the idle-worker sleep coordinator
+    pub fn idle_sleep(&self) -> &Arc<IdleSleep> {
+        &self.idle
+    }
+
+    /// Get the blocking-task pool, for constructing each worker's
+    /// `LocalExecutor` with the same shared pool.
+    pub fn blocking_pool(&self) -> &Arc<BlockingPool> {
+        &self.blocking
+    }
+
     /// Shutdown the runtime
     pub fn shutdown(&self) {
         self.running.store(false, Ordering::Release);
@@ -164,6 +275,61 @@ impl Runtime {
     pub fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
         futures_lite::future::block_on(future)
     }
+
+    /// Run `f` once on every worker thread, blocking until all copies have
+    /// completed, and collect each worker's result into a `Vec` indexed by
+    /// worker id.
+    ///
+    /// See [`WorkerPool::broadcast`] for how jobs are dispatched. Returns an
+    /// empty `Vec` if the runtime has no worker pool installed yet.
+    pub fn broadcast<F, T>(&self, f: F) -> Vec<T>
+    where
+        F: Fn(BroadcastContext) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        match self.workers.read().as_ref() {
+            Some(pool) => pool.broadcast(f),
+            None => Vec::new(),
+        }
+    }
+
+    /// Async variant of [`broadcast`](Self::broadcast) that doesn't block
+    /// the calling thread while waiting for workers to finish.
+    pub fn broadcast_async<F, T>(&self, f: F) -> BroadcastFuture<T>
+    where
+        F: Fn(BroadcastContext) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        match self.workers.read().as_ref() {
+            Some(pool) => pool.broadcast_async(f),
+            None => BroadcastFuture::ready(),
+        }
+    }
+
+    /// Run `f` in a forked, seccomp-filtered child process for untrusted
+    /// FFI, returning its result or a typed error if the child crashed or
+    /// was killed by its own filter.
+    ///
+    /// See [`isolate::run_isolated`] for the fork-safety caveat and the
+    /// critical-section semantics applied while waiting on the child.
+    pub fn spawn_isolated<F>(&self, policy: &SeccompPolicy, f: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce() -> Vec<u8> + std::panic::UnwindSafe,
+    {
+        isolate::run_isolated(policy, f)
+    }
+
+    /// Publish this runtime's cgroup assignment (set via `Builder::cgroup`)
+    /// into `maps`'s `cgroup_weight_map`, so the BPF side's `cgroup_init`/
+    /// `cgroup_set_weight` can scale `dsq_vtime` accounting by it.
+    ///
+    /// A no-op returning `Ok(())` if no cgroup assignment was configured.
+    pub fn apply_cgroup_weight(&self, maps: &BpfMaps) -> Result<()> {
+        match &self.config.cgroup {
+            Some(assignment) => assignment.apply(maps),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Drop for Runtime {
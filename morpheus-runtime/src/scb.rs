@@ -2,51 +2,79 @@
 //!
 //! SCBs are per-worker structures shared between kernel and userspace.
 //! This module provides safe Rust wrappers for SCB access.
+//!
+//! The unsafe part of this module is narrow and isolated: where the bytes
+//! backing a `MorpheusScb` actually come from is abstracted behind
+//! [`ScbBacking`]. The real path ([`ScbMap`]) mmaps the kernel's entire
+//! `scb_map` once and hands out slot views; a second, heap-allocated
+//! implementation ([`ScbHandle::from_mock`]) lets the atomic logic above it -
+//! `yield_requested`, the `acknowledge` CAS, `enter_critical`/
+//! `exit_critical` - run, including under `cargo +nightly miri test`,
+//! without needing `mmap`/`dup`/libbpf, none of which Miri can execute.
 
 use crate::error::{Error, Result};
+use crate::metrics;
 use morpheus_common::{config, MorpheusScb};
 use std::fs::File;
 use std::os::fd::{AsRawFd, BorrowedFd};
 use std::os::unix::io::FromRawFd;
 use std::ptr::NonNull;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 
-/// Handle to a memory-mapped SCB
-///
-/// Provides safe access to an SCB in the kernel's BPF map.
-/// The SCB is memory-mapped for zero-copy access.
-pub struct ScbHandle {
+/// Storage backing an SCB's memory. Implementors only need to hand back a
+/// stable pointer; `ScbHandle` owns the rest of the lifecycle.
+trait ScbBacking: Send + Sync {
+    fn ptr(&self) -> NonNull<MorpheusScb>;
+}
+
+/// The real backing: one slot of a [`ScbMap`]'s single mmap over the
+/// kernel's entire `scb_map`. Cloning the `Arc` keeps the mapping alive for
+/// as long as any handle still points into it.
+struct SlotBacking {
     ptr: NonNull<MorpheusScb>,
-    worker_id: u32,
-    // Keep the mmap alive
-    _mmap: memmap2::MmapMut,
+    _mmap: Arc<memmap2::MmapMut>,
 }
 
-// SCB access is thread-safe through atomics
-unsafe impl Send for ScbHandle {}
-unsafe impl Sync for ScbHandle {}
+// The mmap is only ever read through the SCB's own atomics.
+unsafe impl Send for SlotBacking {}
+unsafe impl Sync for SlotBacking {}
 
-impl ScbHandle {
-    /// Create a new SCB handle by mapping the SCB map
-    ///
-    /// # Arguments
-    /// * `map_fd` - File descriptor of the scb_map BPF map
-    /// * `worker_id` - ID of this worker (index into the map)
-    /// * `escapable` - Whether this worker allows forced escalation
+impl ScbBacking for SlotBacking {
+    fn ptr(&self) -> NonNull<MorpheusScb> {
+        self.ptr
+    }
+}
+
+/// One `mmap` over the kernel's entire `scb_map` region
+/// (`MAX_WORKERS * size_of::<MorpheusScb>()`), shared by every worker's
+/// [`ScbHandle`].
+///
+/// Previously each worker dup'd the map fd and mmap'd its own single-slot
+/// mapping, which meant a fleet of N workers produced N file descriptors and
+/// N independent mappings - extra syscalls and address-space fragmentation
+/// at startup, on top of a `mem::forget(file)` fd leak per worker. `ScbMap`
+/// maps the whole region once; [`ScbMap::worker`] just computes an offset
+/// into it.
+pub struct ScbMap {
+    mmap: Arc<memmap2::MmapMut>,
+    base: NonNull<u8>,
+}
+
+// The mmap is only ever read through each slot's SCB atomics.
+unsafe impl Send for ScbMap {}
+unsafe impl Sync for ScbMap {}
+
+impl ScbMap {
+    /// Map the whole `scb_map` BPF map.
     ///
     /// # Safety
-    /// The caller must ensure the map_fd is valid and points to the scb_map.
-    pub unsafe fn new(map_fd: BorrowedFd<'_>, worker_id: u32, escapable: bool) -> Result<Self> {
-        if worker_id >= config::MAX_WORKERS {
-            return Err(Error::InvalidWorker(worker_id));
-        }
-
-        // Calculate the offset for this worker's SCB
+    /// The caller must ensure `map_fd` is valid and points to the scb_map.
+    pub unsafe fn new(map_fd: BorrowedFd<'_>) -> Result<Self> {
         let scb_size = std::mem::size_of::<MorpheusScb>();
-        let offset = (worker_id as usize) * scb_size;
+        let total_len = scb_size * config::MAX_WORKERS as usize;
 
-        // Memory map the SCB
-        // Note: We map just this worker's SCB, not the entire map
         // Create a File from the borrowed fd for mmap (we need to dup it)
         let raw_fd = map_fd.as_raw_fd();
         let dup_fd = libc::dup(raw_fd);
@@ -56,36 +84,107 @@ impl ScbHandle {
         let file = File::from_raw_fd(dup_fd);
 
         let mmap = memmap2::MmapOptions::new()
-            .offset(offset as u64)
-            .len(scb_size)
+            .len(total_len)
             .map_mut(&file)
             .map_err(Error::Mmap)?;
 
-        // Forget the file to avoid closing the fd (it's owned by libbpf)
+        // Forget the file so the dup'd fd is never closed. The mapping does
+        // not need it, so this leaves one fd open per `ScbMap`.
         std::mem::forget(file);
 
-        let ptr = NonNull::new(mmap.as_ptr() as *mut MorpheusScb)
+        let base = NonNull::new(mmap.as_ptr() as *mut u8)
             .ok_or_else(|| Error::Mmap(std::io::Error::other("mmap returned null")))?;
 
-        // Initialize the SCB
-        let scb = &*ptr.as_ptr();
-        scb.preempt_seq.store(0, Ordering::Release);
-        scb.budget_remaining_ns
-            .store(config::DEFAULT_SLICE_NS, Ordering::Release);
-        scb.kernel_pressure_level.store(0, Ordering::Release);
-        scb.is_in_critical_section.store(0, Ordering::Release);
-        scb.escapable
-            .store(if escapable { 1 } else { 0 }, Ordering::Release);
-        scb.last_ack_seq.store(0, Ordering::Release);
-        scb.runtime_priority.store(500, Ordering::Release);
-
         Ok(Self {
-            ptr,
-            worker_id,
-            _mmap: mmap,
+            mmap: Arc::new(mmap),
+            base,
         })
     }
 
+    /// Hand out a handle into `worker_id`'s slot of the shared mapping.
+    ///
+    /// The whole region was mapped from offset 0, so every slot offset
+    /// (`worker_id * size_of::<MorpheusScb>()`) falls on a natural struct
+    /// boundary within it - there's no per-slot page-alignment fallback to
+    /// worry about the way there would be if each slot had its own mapping.
+    pub fn worker(&self, worker_id: u32, escapable: bool) -> Result<ScbHandle> {
+        if worker_id >= config::MAX_WORKERS {
+            return Err(Error::InvalidWorker(worker_id));
+        }
+
+        let scb_size = std::mem::size_of::<MorpheusScb>();
+        let offset = (worker_id as usize) * scb_size;
+        // SAFETY: offset + scb_size is within the region mapped by `new`,
+        // since worker_id < config::MAX_WORKERS was just checked above.
+        let ptr = unsafe {
+            NonNull::new_unchecked(self.base.as_ptr().add(offset) as *mut MorpheusScb)
+        };
+
+        let handle = ScbHandle {
+            backing: Box::new(SlotBacking {
+                ptr,
+                _mmap: self.mmap.clone(),
+            }),
+            worker_id,
+            cpu_time: CpuTimeTracker::new(),
+            critical_depth: AtomicU32::new(0),
+            saved_priority: AtomicU32::new(0),
+        };
+        init_scb(handle.scb(), escapable);
+        Ok(handle)
+    }
+}
+
+/// A heap-allocated SCB, for tests that want the real atomic behavior
+/// without a kernel, a BPF map, or mmap.
+struct MockBacking {
+    scb: Box<MorpheusScb>,
+}
+
+impl ScbBacking for MockBacking {
+    fn ptr(&self) -> NonNull<MorpheusScb> {
+        NonNull::from(self.scb.as_ref())
+    }
+}
+
+/// Handle to an SCB
+///
+/// Provides safe access to an SCB backed by either the kernel's BPF map
+/// ([`ScbMap::worker`]) or a heap-allocated mock ([`ScbHandle::from_mock`]).
+pub struct ScbHandle {
+    backing: Box<dyn ScbBacking>,
+    worker_id: u32,
+    cpu_time: CpuTimeTracker,
+    /// Nesting depth for `enter_critical`/`enter_critical_with_ceiling`, so
+    /// only the outermost `exit_critical` clears the flag and restores
+    /// priority.
+    critical_depth: AtomicU32,
+    /// `runtime_priority` as of the outermost `enter_critical*` call, to
+    /// restore on the matching `exit_critical`.
+    saved_priority: AtomicU32,
+}
+
+impl ScbHandle {
+    /// Create an SCB handle backed by a heap allocation instead of the
+    /// kernel's BPF map.
+    ///
+    /// Exercises exactly the same atomic logic as the mmap'd path - this is
+    /// the intended way to drive `ScbHandle` under `cargo +nightly miri
+    /// test`, since Miri cannot run `mmap`/`dup`/libbpf.
+    pub fn from_mock(worker_id: u32, escapable: bool) -> Self {
+        let handle = Self {
+            backing: Box::new(MockBacking {
+                scb: Box::new(MorpheusScb::new(escapable)),
+            }),
+            worker_id,
+            cpu_time: CpuTimeTracker::new(),
+            critical_depth: AtomicU32::new(0),
+            saved_priority: AtomicU32::new(0),
+        };
+        init_scb(handle.scb(), escapable);
+        handle
+    }
+
     /// Get the worker ID
     #[inline]
     pub fn worker_id(&self) -> u32 {
@@ -95,8 +194,9 @@ impl ScbHandle {
     /// Get a reference to the SCB
     #[inline]
     pub fn scb(&self) -> &MorpheusScb {
-        // SAFETY: The pointer is valid for the lifetime of this handle
-        unsafe { self.ptr.as_ref() }
+        // SAFETY: The backing guarantees its pointer is valid for the
+        // lifetime of this handle.
+        unsafe { self.backing.ptr().as_ref() }
     }
 
     /// Check if a yield was requested
@@ -131,18 +231,64 @@ impl ScbHandle {
     /// Enter a critical section
     ///
     /// While in a critical section, the kernel will not escalate.
-    /// Returns the previous critical section state.
+    /// Returns the previous critical section state. Supports nesting: the
+    /// flag is only raised on the outermost entry.
     #[inline]
     pub fn enter_critical(&self) -> u32 {
-        let scb = self.scb();
-        scb.is_in_critical_section.swap(1, Ordering::Release)
+        let current_priority = self.scb().runtime_priority.load(Ordering::Relaxed);
+        self.enter_critical_with_ceiling(current_priority)
+    }
+
+    /// Enter a critical section with a priority-inheritance ceiling.
+    ///
+    /// A worker holding a contended lock still advertises its normal
+    /// `runtime_priority`, so the kernel scheduler has no particular reason
+    /// to keep it running - it can still be preempted at budget expiry,
+    /// lengthening the critical section for everyone waiting on it. This
+    /// saves the current priority, raises it to `ceiling`
+    /// (capped at 1000), and sets the critical section flag.
+    ///
+    /// Supports nesting via a depth counter, like `enter_critical`: only
+    /// the outermost call saves the priority to restore and raises the
+    /// flag; inner calls just bump the depth. Returns the previous
+    /// critical section state.
+    #[inline]
+    pub fn enter_critical_with_ceiling(&self, ceiling: u32) -> u32 {
+        let depth = self.critical_depth.fetch_add(1, Ordering::AcqRel);
+        if depth > 0 {
+            return 1;
+        }
+
+        let current = self.scb().runtime_priority.load(Ordering::Relaxed);
+        self.saved_priority.store(current, Ordering::Relaxed);
+
+        let ceiling = ceiling.min(1000);
+        if ceiling > current {
+            self.set_priority(ceiling);
+            metrics::metrics().record_priority_donation(self.worker_id);
+        }
+
+        self.scb().is_in_critical_section.swap(1, Ordering::Release)
     }
 
     /// Exit a critical section
+    ///
+    /// Only the outermost exit (matching the outermost `enter_critical`/
+    /// `enter_critical_with_ceiling`) clears the critical flag and restores
+    /// the priority saved on entry.
     #[inline]
     pub fn exit_critical(&self) {
-        let scb = self.scb();
-        scb.is_in_critical_section.store(0, Ordering::Release);
+        let depth = self.critical_depth.fetch_sub(1, Ordering::AcqRel);
+        debug_assert!(
+            depth > 0,
+            "exit_critical called without a matching enter_critical"
+        );
+        if depth > 1 {
+            return;
+        }
+
+        self.set_priority(self.saved_priority.load(Ordering::Relaxed));
+        self.scb().is_in_critical_section.store(0, Ordering::Release);
     }
 
     /// Get the current kernel pressure level (0-100)
@@ -164,7 +310,298 @@ impl ScbHandle {
             .runtime_priority
             .store(priority.min(1000), Ordering::Release);
     }
+
+    /// Request an uninterrupted timeslice of `ns` nanoseconds.
+    ///
+    /// Advisory: lets the kernel size cgroup throttling for a worker that
+    /// is about to enter a critical section, instead of relying solely on
+    /// `is_in_critical_section`. Pass 0 to clear a previous request.
+    #[inline]
+    pub fn request_timeslice_ns(&self, ns: u64) {
+        self.scb()
+            .requested_timeslice_ns
+            .store(ns, Ordering::Release);
+    }
+
+    /// Get the currently requested timeslice in nanoseconds (0 if none).
+    #[inline]
+    pub fn requested_timeslice_ns(&self) -> u64 {
+        self.scb().requested_timeslice_ns.load(Ordering::Relaxed)
+    }
+
+    /// Publish the bitmask of CPUs this worker is pinned to (bit N = CPU N,
+    /// up to 64), or 0 to mark the worker unpinned.
+    ///
+    /// This mirrors whatever `sched_setaffinity` was actually given so the
+    /// BPF side's `select_cpu`/`set_cpumask` path can cooperate with the
+    /// same placement instead of re-deriving it from `/proc`.
+    #[inline]
+    pub fn set_cpu_mask(&self, mask: u64) {
+        self.scb().assigned_cpu_mask.store(mask, Ordering::Release);
+    }
+
+    /// Get the bitmask published by [`set_cpu_mask`](Self::set_cpu_mask),
+    /// or 0 if the worker is unpinned.
+    #[inline]
+    pub fn cpu_mask(&self) -> u64 {
+        self.scb().assigned_cpu_mask.load(Ordering::Relaxed)
+    }
+
+    /// Record this worker transitioning onto the CPU (analogous to the
+    /// kernel's `running` callback): the elapsed time since the last
+    /// transition is charged to `off_cpu_ns`.
+    #[inline]
+    pub fn mark_running(&self) {
+        self.cpu_time.mark_running();
+    }
+
+    /// Record this worker transitioning off the CPU, e.g. about to park
+    /// waiting for work (analogous to the kernel's `stopping` callback):
+    /// the elapsed time since the last transition is charged to
+    /// `on_cpu_ns` and `vtime`.
+    #[inline]
+    pub fn mark_stopping(&self) {
+        self.cpu_time.mark_stopping();
+    }
+
+    /// Record a voluntary yield at a checkpoint (the worker cooperated with
+    /// a kernel or defensive-mode yield request).
+    #[inline]
+    pub fn record_voluntary_yield(&self) {
+        self.cpu_time.record_voluntary_yield();
+    }
+
+    /// Record an involuntary/forced preemption.
+    #[inline]
+    pub fn record_forced_preempt(&self) {
+        self.cpu_time.record_forced_preempt();
+    }
+
+    /// Snapshot this worker's accumulated on/off-CPU time, vtime, and yield
+    /// counters.
+    #[inline]
+    pub fn cputime_stats(&self) -> WorkerStats {
+        self.cpu_time.snapshot()
+    }
+}
+
+/// Snapshot of a worker's scheduler-attributed cputime accounting,
+/// analogous to how the kernel tracks running vs. stopping intervals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Total nanoseconds spent actually running on a CPU.
+    pub on_cpu_ns: u64,
+    /// Total nanoseconds spent off-CPU while parked waiting for work.
+    pub off_cpu_ns: u64,
+    /// Virtual time accumulated while on-CPU (nanoseconds).
+    pub vtime: u64,
+    /// Count of voluntary yields at a checkpoint.
+    pub voluntary_yields: u64,
+    /// Count of involuntary/forced preemptions.
+    pub forced_preempts: u64,
+}
+
+/// Process-wide monotonic epoch so transition timestamps fit in a `u64` of
+/// nanoseconds instead of a full `Instant`.
+fn process_epoch() -> &'static Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now)
+}
+
+fn now_ns() -> u64 {
+    process_epoch().elapsed().as_nanos() as u64
+}
+
+/// Tracks on-CPU/off-CPU nanoseconds, vtime, and yield counters for one
+/// worker. Running/stopping transitions bracket the executor's busy loop
+/// (see `LocalExecutor::run`); voluntary yields and forced preempts are
+/// independent counters bumped at their own call sites.
+#[derive(Debug)]
+struct CpuTimeTracker {
+    on_cpu_ns: AtomicU64,
+    off_cpu_ns: AtomicU64,
+    vtime: AtomicU64,
+    voluntary_yields: AtomicU64,
+    forced_preempts: AtomicU64,
+    last_transition_ns: AtomicU64,
+}
+
+impl CpuTimeTracker {
+    fn new() -> Self {
+        Self {
+            on_cpu_ns: AtomicU64::new(0),
+            off_cpu_ns: AtomicU64::new(0),
+            vtime: AtomicU64::new(0),
+            voluntary_yields: AtomicU64::new(0),
+            forced_preempts: AtomicU64::new(0),
+            last_transition_ns: AtomicU64::new(now_ns()),
+        }
+    }
+
+    fn mark_running(&self) {
+        let now = now_ns();
+        let prev = self.last_transition_ns.swap(now, Ordering::AcqRel);
+        self.off_cpu_ns
+            .fetch_add(now.saturating_sub(prev), Ordering::Relaxed);
+    }
+
+    fn mark_stopping(&self) {
+        let now = now_ns();
+        let prev = self.last_transition_ns.swap(now, Ordering::AcqRel);
+        let elapsed = now.saturating_sub(prev);
+        self.on_cpu_ns.fetch_add(elapsed, Ordering::Relaxed);
+        self.vtime.fetch_add(elapsed, Ordering::Relaxed);
+    }
+
+    fn record_voluntary_yield(&self) {
+        self.voluntary_yields.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_forced_preempt(&self) {
+        self.forced_preempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> WorkerStats {
+        WorkerStats {
+            on_cpu_ns: self.on_cpu_ns.load(Ordering::Relaxed),
+            off_cpu_ns: self.off_cpu_ns.load(Ordering::Relaxed),
+            vtime: self.vtime.load(Ordering::Relaxed),
+            voluntary_yields: self.voluntary_yields.load(Ordering::Relaxed),
+            forced_preempts: self.forced_preempts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Reset an SCB to its initial state, shared by both `ScbMap::worker` and
+/// `ScbHandle::from_mock` so the two backings start identically.
+fn init_scb(scb: &MorpheusScb, escapable: bool) {
+    scb.preempt_seq.store(0, Ordering::Release);
+    scb.budget_remaining_ns
+        .store(config::DEFAULT_SLICE_NS, Ordering::Release);
+    scb.kernel_pressure_level.store(0, Ordering::Release);
+    scb.is_in_critical_section.store(0, Ordering::Release);
+    scb.escapable
+        .store(if escapable { 1 } else { 0 }, Ordering::Release);
+    scb.last_ack_seq.store(0, Ordering::Release);
+    scb.runtime_priority.store(500, Ordering::Release);
+    scb.requested_timeslice_ns.store(0, Ordering::Release);
+    scb.assigned_cpu_mask.store(0, Ordering::Release);
 }
 
-// Note: For creating SCB handles from libbpf-rs maps, use ScbHandle::new()
-// directly with the map's file descriptor.
+// Note: For creating SCB handles from libbpf-rs maps, map the scb_map once
+// with ScbMap::new() and call ScbMap::worker() per worker.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering as O;
+
+    #[test]
+    fn test_from_mock_starts_with_no_yield_requested() {
+        let handle = ScbHandle::from_mock(0, true);
+        assert!(!handle.yield_requested());
+        assert_eq!(handle.worker_id(), 0);
+        assert_eq!(handle.scb().escapable.load(O::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_acknowledge_clears_yield_requested() {
+        let handle = ScbHandle::from_mock(1, true);
+        handle.scb().preempt_seq.store(5, O::Release);
+
+        assert!(handle.yield_requested());
+        assert!(handle.acknowledge());
+        assert!(!handle.yield_requested());
+    }
+
+    #[test]
+    fn test_acknowledge_is_idempotent_once_caught_up() {
+        let handle = ScbHandle::from_mock(2, true);
+        handle.scb().preempt_seq.store(3, O::Release);
+
+        assert!(handle.acknowledge());
+        // No new preempt_seq since: already acknowledged, still succeeds.
+        assert!(handle.acknowledge());
+        assert!(!handle.yield_requested());
+    }
+
+    #[test]
+    fn test_critical_section_enter_exit_round_trips() {
+        let handle = ScbHandle::from_mock(3, true);
+
+        let previous = handle.enter_critical();
+        assert_eq!(previous, 0, "should not have been in a critical section");
+        assert_eq!(handle.scb().is_in_critical_section.load(O::Relaxed), 1);
+
+        handle.exit_critical();
+        assert_eq!(handle.scb().is_in_critical_section.load(O::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_cpu_mask_round_trips() {
+        let handle = ScbHandle::from_mock(4, true);
+        assert_eq!(handle.cpu_mask(), 0);
+
+        handle.set_cpu_mask(0b1011);
+        assert_eq!(handle.cpu_mask(), 0b1011);
+    }
+
+    #[test]
+    fn test_cputime_tracking_credits_on_cpu_and_vtime_together() {
+        let handle = ScbHandle::from_mock(5, true);
+        handle.mark_stopping();
+
+        let stats = handle.cputime_stats();
+        // mark_stopping charges the same elapsed interval to both counters.
+        assert_eq!(stats.on_cpu_ns, stats.vtime);
+        assert_eq!(stats.off_cpu_ns, 0);
+    }
+
+    #[test]
+    fn test_enter_critical_with_ceiling_raises_and_restores_priority() {
+        let handle = ScbHandle::from_mock(6, true);
+        handle.set_priority(400);
+
+        handle.enter_critical_with_ceiling(900);
+        assert_eq!(handle.scb().runtime_priority.load(O::Relaxed), 900);
+        assert_eq!(handle.scb().is_in_critical_section.load(O::Relaxed), 1);
+
+        handle.exit_critical();
+        assert_eq!(handle.scb().runtime_priority.load(O::Relaxed), 400);
+        assert_eq!(handle.scb().is_in_critical_section.load(O::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_nested_critical_sections_only_restore_on_outermost_exit() {
+        let handle = ScbHandle::from_mock(7, true);
+        handle.set_priority(200);
+
+        handle.enter_critical_with_ceiling(1000);
+        handle.enter_critical(); // nested, no new ceiling
+        assert_eq!(handle.scb().runtime_priority.load(O::Relaxed), 1000);
+
+        handle.exit_critical(); // inner exit: must not restore yet
+        assert_eq!(handle.scb().runtime_priority.load(O::Relaxed), 1000);
+        assert_eq!(handle.scb().is_in_critical_section.load(O::Relaxed), 1);
+
+        handle.exit_critical(); // outer exit: restores
+        assert_eq!(handle.scb().runtime_priority.load(O::Relaxed), 200);
+        assert_eq!(handle.scb().is_in_critical_section.load(O::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_ceiling_below_current_priority_does_not_lower_it() {
+        let handle = ScbHandle::from_mock(8, true);
+        handle.set_priority(700);
+
+        handle.enter_critical_with_ceiling(100);
+        assert_eq!(
+            handle.scb().runtime_priority.load(O::Relaxed),
+            700,
+            "a ceiling below the current priority should not lower it"
+        );
+
+        handle.exit_critical();
+        assert_eq!(handle.scb().runtime_priority.load(O::Relaxed), 700);
+    }
+}
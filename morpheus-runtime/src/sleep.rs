@@ -0,0 +1,209 @@
+//! Idle-worker sleep coordination
+//!
+//! Ports the shape of rayon-core's sleep module: workers that find no work
+//! spin for a configurable number of rounds, then park on a condvar rather
+//! than busy-polling the global `Injector`. A monotonic "jobs event
+//! counter" (JEC) is bumped by every `spawn`/`injector.push`/hint arrival;
+//! a worker about to sleep captures the JEC it last observed and only
+//! actually parks if the JEC hasn't moved since, which closes the
+//! lost-wakeup race between "no work found" and "work pushed" without
+//! needing the waker to know exactly which threads are asleep yet.
+//!
+//! # Design
+//!
+//! All bookkeeping lives in one `AtomicU64`, packed as:
+//! - bits 0..16:  number of sleeping threads
+//! - bits 16..32: number of inactive (not actively polling) threads
+//! - bits 32..64: jobs event counter (JEC)
+//!
+//! Threads block on a single `Condvar`; a waker that wants to wake `n`
+//! sleepers calls `notify_one()` `n` times rather than `notify_all()`, so a
+//! burst of small work doesn't thunder-herd every idle worker.
+
+use parking_lot::{Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const SLEEPING_BITS: u32 = 16;
+const INACTIVE_BITS: u32 = 16;
+const SLEEPING_MASK: u64 = (1 << SLEEPING_BITS) - 1;
+const INACTIVE_MASK: u64 = (1 << INACTIVE_BITS) - 1;
+const JEC_SHIFT: u32 = SLEEPING_BITS + INACTIVE_BITS;
+
+fn unpack(word: u64) -> (u64, u64, u64) {
+    let sleeping = word & SLEEPING_MASK;
+    let inactive = (word >> SLEEPING_BITS) & INACTIVE_MASK;
+    let jec = word >> JEC_SHIFT;
+    (sleeping, inactive, jec)
+}
+
+fn pack(sleeping: u64, inactive: u64, jec: u64) -> u64 {
+    (jec << JEC_SHIFT) | ((inactive & INACTIVE_MASK) << SLEEPING_BITS) | (sleeping & SLEEPING_MASK)
+}
+
+/// Tunables controlling how eagerly idle workers give up the CPU.
+#[derive(Debug, Clone, Copy)]
+pub struct SleepConfig {
+    /// Spin rounds attempted (re-checking local queue, injector, then
+    /// stealers each round) before a worker with no work parks.
+    pub spin_rounds: u32,
+
+    /// Maximum number of sleepers woken by a single `new_work()` call.
+    pub wake_batch_size: u32,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            spin_rounds: 32,
+            wake_batch_size: 1,
+        }
+    }
+}
+
+/// Coordinates idle workers parking and waking across a `WorkerPool`.
+pub struct IdleSleep {
+    config: SleepConfig,
+    state: AtomicU64,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+impl IdleSleep {
+    /// Create a new coordinator with the given tunables.
+    pub fn new(config: SleepConfig) -> Self {
+        Self {
+            config,
+            state: AtomicU64::new(pack(0, 0, 0)),
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Current jobs event counter, for a worker about to spin to capture
+    /// before its spin loop and compare against after.
+    pub fn jobs_event_counter(&self) -> u64 {
+        unpack(self.state.load(Ordering::Acquire)).2
+    }
+
+    /// Number of configured spin rounds before a worker should sleep.
+    pub fn spin_rounds(&self) -> u32 {
+        self.config.spin_rounds
+    }
+
+    /// Call after a spin round finds no work. Returns `true` if the worker
+    /// should keep spinning (round budget not exhausted and JEC hasn't
+    /// moved), `false` if it should call [`sleep`](Self::sleep).
+    pub fn no_work_found(&self, round: u32, observed_jec: u64) -> bool {
+        round < self.config.spin_rounds && self.jobs_event_counter() == observed_jec
+    }
+
+    /// Park the calling thread until new work is announced or the observed
+    /// JEC has moved, whichever comes first. Takes the JEC observed before
+    /// the spin loop started: if it has already changed, returns
+    /// immediately instead of sleeping through a racing wakeup.
+    pub fn sleep(&self, observed_jec: u64) {
+        let guard = self.lock.lock();
+
+        if self.jobs_event_counter() != observed_jec {
+            return;
+        }
+
+        self.state.fetch_add(1, Ordering::AcqRel); // sleeping += 1
+        let mut guard = guard;
+        self.condvar.wait(&mut guard);
+        self.state.fetch_sub(1, Ordering::AcqRel); // sleeping -= 1
+    }
+
+    /// Announce new work: bumps the JEC and wakes up to `wake_batch_size`
+    /// sleeping workers. Called from `spawn`, `injector.push`, and hint
+    /// arrival.
+    pub fn new_work(&self) {
+        self.state.fetch_add(1 << JEC_SHIFT, Ordering::AcqRel);
+
+        let _guard = self.lock.lock();
+        let sleeping = unpack(self.state.load(Ordering::Acquire)).0;
+        let wake = sleeping.min(self.config.wake_batch_size as u64);
+        for _ in 0..wake {
+            self.condvar.notify_one();
+        }
+    }
+
+    /// Mark a worker as inactive (idle, not currently polling for work).
+    pub fn mark_inactive(&self) {
+        self.state.fetch_add(1 << SLEEPING_BITS, Ordering::AcqRel);
+    }
+
+    /// Mark a previously-inactive worker as active again.
+    pub fn mark_active(&self) {
+        self.state.fetch_sub(1 << SLEEPING_BITS, Ordering::AcqRel);
+    }
+
+    /// Number of threads currently parked.
+    pub fn sleeping_count(&self) -> u64 {
+        unpack(self.state.load(Ordering::Acquire)).0
+    }
+
+    /// Number of threads currently marked inactive.
+    pub fn inactive_count(&self) -> u64 {
+        unpack(self.state.load(Ordering::Acquire)).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_roundtrip() {
+        let word = pack(3, 5, 1234);
+        assert_eq!(unpack(word), (3, 5, 1234));
+    }
+
+    #[test]
+    fn test_new_work_bumps_jec() {
+        let sleep = IdleSleep::new(SleepConfig::default());
+        let before = sleep.jobs_event_counter();
+        sleep.new_work();
+        assert_eq!(sleep.jobs_event_counter(), before + 1);
+    }
+
+    #[test]
+    fn test_no_work_found_stops_after_spin_rounds() {
+        let sleep = IdleSleep::new(SleepConfig {
+            spin_rounds: 2,
+            wake_batch_size: 1,
+        });
+        let jec = sleep.jobs_event_counter();
+        assert!(sleep.no_work_found(0, jec));
+        assert!(sleep.no_work_found(1, jec));
+        assert!(!sleep.no_work_found(2, jec));
+    }
+
+    #[test]
+    fn test_no_work_found_false_once_jec_moves() {
+        let sleep = IdleSleep::new(SleepConfig::default());
+        let jec = sleep.jobs_event_counter();
+        sleep.new_work();
+        assert!(!sleep.no_work_found(0, jec));
+    }
+
+    #[test]
+    fn test_sleep_wakes_on_new_work() {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let sleep = Arc::new(IdleSleep::new(SleepConfig::default()));
+        let jec = sleep.jobs_event_counter();
+
+        let sleeper = {
+            let sleep = sleep.clone();
+            std::thread::spawn(move || sleep.sleep(jec))
+        };
+
+        // Give the sleeper a chance to actually park before waking it.
+        std::thread::sleep(Duration::from_millis(20));
+        sleep.new_work();
+
+        sleeper.join().expect("sleeper thread panicked");
+    }
+}
@@ -0,0 +1,582 @@
+//! Checkpoint-cooperative async synchronization primitives
+//!
+//! `Mutex`, `Condvar`, and `RwLock` whose wait paths cooperate with Morpheus
+//! checkpoints: a waiter spins briefly, then registers on a FIFO waiter
+//! queue and acknowledges the current yield hint (so the kernel sees a
+//! worker that's voluntarily parked, not one that's gone unresponsive)
+//! before descheduling. Unlock releases the lock and then wakes the oldest
+//! waiter; the lock is not handed to that waiter, so a task that never
+//! queued can still take it first.
+//!
+//! Blocking on any of these while [`in_critical_section`] is true is the
+//! exact adversarial case the liar benchmark exists to catch: a critical
+//! section is supposed to run to completion without descheduling, so
+//! blocking inside one is denied with a debug assertion rather than
+//! silently stalling the worker.
+
+use crate::critical::in_critical_section;
+use crate::worker;
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::task::{Context, Poll, Waker};
+
+/// Spin iterations attempted before a waiter enqueues and parks.
+const SPIN_ITERS: u32 = 32;
+
+struct Waiter {
+    woken: AtomicBool,
+    waker: StdMutex<Option<Waker>>,
+}
+
+impl Waiter {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            woken: AtomicBool::new(false),
+            waker: StdMutex::new(None),
+        })
+    }
+
+    fn wake(&self) {
+        self.woken.store(true, Ordering::Release);
+        if let Some(w) = self.waker.lock().unwrap().take() {
+            w.wake();
+        }
+    }
+}
+
+/// FIFO queue of parked waiters, shared by `Mutex`, `Condvar`, and `RwLock`.
+struct WaitQueue {
+    waiters: StdMutex<VecDeque<Arc<Waiter>>>,
+}
+
+impl WaitQueue {
+    fn new() -> Self {
+        Self {
+            waiters: StdMutex::new(VecDeque::new()),
+        }
+    }
+
+    fn enqueue(&self) -> Arc<Waiter> {
+        let waiter = Waiter::new();
+        self.waiters.lock().unwrap().push_back(waiter.clone());
+        waiter
+    }
+
+    /// Wake the oldest waiter. Returns whether a waiter was woken.
+    fn wake_one(&self) -> bool {
+        if let Some(waiter) = self.waiters.lock().unwrap().pop_front() {
+            waiter.wake();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove `waiter` from the queue if it's still there. Used to undo an
+    /// `enqueue` when a re-check right after it finds the lock acquirable
+    /// after all, so a waiter that never actually parks doesn't eat a
+    /// future `wake_one` meant for someone else.
+    fn remove(&self, waiter: &Arc<Waiter>) {
+        self.waiters
+            .lock()
+            .unwrap()
+            .retain(|w| !Arc::ptr_eq(w, waiter));
+    }
+
+    fn wake_all(&self) {
+        let waiters: Vec<_> = self.waiters.lock().unwrap().drain(..).collect();
+        for waiter in waiters {
+            waiter.wake();
+        }
+    }
+}
+
+struct ParkFuture {
+    waiter: Arc<Waiter>,
+}
+
+impl Future for ParkFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.waiter.woken.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        *self.waiter.waker.lock().unwrap() = Some(cx.waker().clone());
+        if self.waiter.woken.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Tells the kernel this worker is voluntarily parking on a sync
+/// primitive rather than stuck unresponsive, and denies doing so from
+/// inside a critical section.
+fn prepare_to_park() {
+    debug_assert!(
+        !in_critical_section(),
+        "blocking on an async sync primitive while in a critical section"
+    );
+    if let Some(scb) = worker::try_current_scb() {
+        scb.acknowledge();
+    }
+}
+
+/// Parks on `queue`, but re-checks `try_acquire` after enqueueing and
+/// before awaiting. Without this, the failed `try_acquire` a caller does
+/// right before parking and the `enqueue` below it are two separate
+/// steps; an unlock racing in the gap between them calls `wake_one`
+/// against a still-empty queue and the wakeup is lost forever, since
+/// nothing else is going to unlock this mutex/rwlock again. Re-checking
+/// after we're in the queue closes that window: any racing unlock either
+/// lands before the re-check (and we see the resource free here) or
+/// after (and wakes the waiter we already queued). Returns `true` if the
+/// re-check acquired the resource directly, in which case the caller
+/// never parked and its (now unnecessary) queue entry has been removed.
+async fn park_on_unless(queue: &WaitQueue, mut try_acquire: impl FnMut() -> bool) -> bool {
+    prepare_to_park();
+    let waiter = queue.enqueue();
+    if try_acquire() {
+        queue.remove(&waiter);
+        return true;
+    }
+    ParkFuture { waiter }.await;
+    false
+}
+
+/// Checkpoint-cooperative async mutex.
+pub struct Mutex<T> {
+    locked: AtomicBool,
+    queue: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Mutex<T> {}
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Create a new, unlocked mutex around `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            queue: WaitQueue::new(),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    fn try_lock_raw(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Try to acquire the lock without waiting.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.try_lock_raw().then_some(MutexGuard { mutex: self })
+    }
+
+    /// Acquire the lock, spinning briefly before parking if contended.
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        for _ in 0..SPIN_ITERS {
+            if self.try_lock_raw() {
+                return MutexGuard { mutex: self };
+            }
+            std::hint::spin_loop();
+        }
+
+        loop {
+            if self.try_lock_raw() {
+                return MutexGuard { mutex: self };
+            }
+            if park_on_unless(&self.queue, || self.try_lock_raw()).await {
+                return MutexGuard { mutex: self };
+            }
+        }
+    }
+}
+
+/// RAII guard returned by [`Mutex::lock`]/[`Mutex::try_lock`].
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.locked.store(false, Ordering::Release);
+        // Wake the oldest waiter. The lock is already released, so any
+        // task's CAS can still take it first; a waiter that loses that race
+        // queues again at the back.
+        self.mutex.queue.wake_one();
+    }
+}
+
+/// Checkpoint-cooperative async condition variable, used with a
+/// [`Mutex`] guard.
+pub struct Condvar {
+    queue: WaitQueue,
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Condvar {
+    /// Create a new condition variable.
+    pub fn new() -> Self {
+        Self {
+            queue: WaitQueue::new(),
+        }
+    }
+
+    /// Release `guard`, wait for a notification, then reacquire the lock.
+    ///
+    /// Like `std::sync::Condvar`, spurious wakeups are possible: callers
+    /// should recheck their condition in a loop, or use
+    /// [`wait_while`](Self::wait_while).
+    pub async fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = guard.mutex;
+        // Enqueue *before* releasing the lock, not after: a notifier is
+        // only correctly synchronized if it mutates the protected state
+        // and calls notify_* while holding (or having just held) this
+        // same mutex, so it can't run until we drop `guard`. Enqueueing
+        // first guarantees it never finds an empty queue - the same class
+        // of lost-wakeup race `park_on_unless` closes for Mutex/RwLock,
+        // just closed here by ordering instead of a re-check, since `wait`
+        // has no boolean predicate of its own to re-poll.
+        prepare_to_park();
+        let waiter = self.queue.enqueue();
+        drop(guard);
+        ParkFuture { waiter }.await;
+        mutex.lock().await
+    }
+
+    /// Wait until `predicate` returns `false`, reacquiring the lock and
+    /// rechecking after every wakeup (handling spurious wakeups).
+    pub async fn wait_while<'a, T, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut predicate: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while predicate(&mut guard) {
+            guard = self.wait(guard).await;
+        }
+        guard
+    }
+
+    /// Wake one waiting task.
+    pub fn notify_one(&self) {
+        self.queue.wake_one();
+    }
+
+    /// Wake all waiting tasks.
+    pub fn notify_all(&self) {
+        self.queue.wake_all();
+    }
+}
+
+const RW_WRITE_LOCKED: i64 = -1;
+const RW_UNLOCKED: i64 = 0;
+
+/// Checkpoint-cooperative async reader-writer lock.
+pub struct RwLock<T> {
+    /// `RW_UNLOCKED`, `RW_WRITE_LOCKED`, or a positive reader count.
+    state: AtomicI64,
+    queue: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for RwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    /// Create a new, unlocked `RwLock` around `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            state: AtomicI64::new(RW_UNLOCKED),
+            queue: WaitQueue::new(),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    fn try_read_raw(&self) -> bool {
+        let mut current = self.state.load(Ordering::Relaxed);
+        loop {
+            if current == RW_WRITE_LOCKED {
+                return false;
+            }
+            match self.state.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(observed) => current = observed,
+            }
+        }
+    }
+
+    fn try_write_raw(&self) -> bool {
+        self.state
+            .compare_exchange(
+                RW_UNLOCKED,
+                RW_WRITE_LOCKED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+
+    /// Acquire a shared (read) lock, spinning briefly before parking.
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        for _ in 0..SPIN_ITERS {
+            if self.try_read_raw() {
+                return RwLockReadGuard { lock: self };
+            }
+            std::hint::spin_loop();
+        }
+        loop {
+            if self.try_read_raw() {
+                return RwLockReadGuard { lock: self };
+            }
+            if park_on_unless(&self.queue, || self.try_read_raw()).await {
+                return RwLockReadGuard { lock: self };
+            }
+        }
+    }
+
+    /// Acquire an exclusive (write) lock, spinning briefly before parking.
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        for _ in 0..SPIN_ITERS {
+            if self.try_write_raw() {
+                return RwLockWriteGuard { lock: self };
+            }
+            std::hint::spin_loop();
+        }
+        loop {
+            if self.try_write_raw() {
+                return RwLockWriteGuard { lock: self };
+            }
+            if park_on_unless(&self.queue, || self.try_write_raw()).await {
+                return RwLockWriteGuard { lock: self };
+            }
+        }
+    }
+}
+
+/// RAII guard returned by [`RwLock::read`].
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let prev = self.lock.state.fetch_sub(1, Ordering::Release);
+        if prev == 1 {
+            // Last reader out: wake a waiter (could be a reader or writer).
+            self.lock.queue.wake_one();
+        }
+    }
+}
+
+/// RAII guard returned by [`RwLock::write`].
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.store(RW_UNLOCKED, Ordering::Release);
+        self.lock.queue.wake_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+
+    #[test]
+    fn test_mutex_basic_lock_unlock() {
+        let mutex = Mutex::new(0u32);
+        block_on(async {
+            {
+                let mut guard = mutex.lock().await;
+                *guard += 1;
+            }
+            let guard = mutex.lock().await;
+            assert_eq!(*guard, 1);
+        });
+    }
+
+    #[test]
+    fn test_mutex_try_lock_fails_while_held() {
+        let mutex = Mutex::new(());
+        let _guard = mutex.try_lock().unwrap();
+        assert!(mutex.try_lock().is_none());
+    }
+
+    #[test]
+    fn test_rwlock_allows_concurrent_readers() {
+        let lock = RwLock::new(42);
+        block_on(async {
+            let r1 = lock.read().await;
+            let r2 = lock.read().await;
+            assert_eq!(*r1, 42);
+            assert_eq!(*r2, 42);
+        });
+    }
+
+    #[test]
+    fn test_rwlock_write_then_read_sees_update() {
+        let lock = RwLock::new(0);
+        block_on(async {
+            {
+                let mut w = lock.write().await;
+                *w = 7;
+            }
+            let r = lock.read().await;
+            assert_eq!(*r, 7);
+        });
+    }
+
+    #[test]
+    fn test_condvar_notify_wakes_waiter() {
+        let mutex = Arc::new(Mutex::new(false));
+        let condvar = Arc::new(Condvar::new());
+
+        let waiter = {
+            let mutex = mutex.clone();
+            let condvar = condvar.clone();
+            std::thread::spawn(move || {
+                block_on(async {
+                    let guard = mutex.lock().await;
+                    let guard = condvar.wait_while(guard, |ready| !*ready).await;
+                    assert!(*guard);
+                });
+            })
+        };
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        block_on(async {
+            let mut guard = mutex.lock().await;
+            *guard = true;
+            drop(guard);
+            condvar.notify_one();
+        });
+
+        waiter.join().expect("waiter thread panicked");
+    }
+
+    #[test]
+    fn test_mutex_contended_lock_never_deadlocks() {
+        // Regression test for a lost-wakeup: a waiter's failed try_lock_raw
+        // and its enqueue used to be two separate steps, so an unlock
+        // racing in between could call wake_one against an empty queue and
+        // never wake anyone again. Unlike test_condvar_notify_wakes_waiter,
+        // this doesn't rely on a sleep to order events - it hammers the
+        // mutex from several threads so the race window gets hit for real.
+        let mutex = Arc::new(Mutex::new(0u64));
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let mutex = mutex.clone();
+                std::thread::spawn(move || {
+                    block_on(async {
+                        for _ in 0..2000 {
+                            let mut guard = mutex.lock().await;
+                            *guard += 1;
+                        }
+                    });
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().expect("worker thread panicked");
+        }
+
+        assert_eq!(*block_on(mutex.lock()), 8 * 2000);
+    }
+
+    #[test]
+    fn test_condvar_wait_never_misses_a_racing_notify() {
+        // Regression test for a lost wakeup: `wait` used to drop the guard
+        // and only enqueue afterwards (inside park_on), so a notifier
+        // racing in that gap could mutate the state, call notify_one, and
+        // find an empty queue - losing the wakeup forever. No sleep here
+        // (unlike test_condvar_notify_wakes_waiter) so the race window
+        // actually gets hit instead of being ordered around.
+        let mutex = Arc::new(Mutex::new(0u64));
+        let condvar = Arc::new(Condvar::new());
+
+        let waiter = {
+            let mutex = mutex.clone();
+            let condvar = condvar.clone();
+            std::thread::spawn(move || {
+                block_on(async {
+                    for expected in 1..=2000u64 {
+                        let guard = mutex.lock().await;
+                        let guard = condvar.wait_while(guard, |v| *v != expected).await;
+                        assert_eq!(*guard, expected);
+                    }
+                });
+            })
+        };
+
+        block_on(async {
+            for next in 1..=2000u64 {
+                let mut guard = mutex.lock().await;
+                *guard = next;
+                drop(guard);
+                condvar.notify_one();
+            }
+        });
+
+        waiter.join().expect("waiter thread panicked");
+    }
+}
@@ -3,13 +3,32 @@
 //! Each worker thread owns one SCB and runs a local async executor.
 //! Workers are registered with the kernel via the worker_tid_map.
 
+use crate::affinity::{self, Affinity, CpuSet};
+use crate::executor::{self, ExecutorStats, Job, LocalExecutor};
 use crate::scb::ScbHandle;
-use parking_lot::Mutex;
+use async_task::{Runnable, Task};
+use crossbeam::deque::Injector;
+use crossbeam::queue::SegQueue;
+use parking_lot::{Condvar, Mutex};
 use std::cell::RefCell;
+use std::future::Future;
 use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
 use std::thread::JoinHandle;
 
+/// Context passed to a [`Runtime::broadcast`](crate::Runtime::broadcast) closure.
+#[derive(Debug, Clone, Copy)]
+pub struct BroadcastContext {
+    /// Index of the worker running this closure.
+    pub worker_id: u32,
+}
+
+/// A broadcast job queued on one worker's dedicated queue.
+pub(crate) type BroadcastJob = Box<dyn FnOnce(BroadcastContext) + Send>;
+
 thread_local! {
     /// The SCB handle for the current worker thread
     static CURRENT_SCB: RefCell<Option<Arc<ScbHandle>>> = const { RefCell::new(None) };
@@ -59,6 +78,20 @@ pub struct Worker {
     /// SCB handle
     pub scb: Arc<ScbHandle>,
 
+    /// This worker's local work-stealing executor: its own deque, stealer
+    /// handles into every sibling's deque, and a handle to the shared
+    /// injector.
+    pub(crate) executor: Arc<LocalExecutor>,
+
+    /// Dedicated broadcast job queue for this worker, drained by its run
+    /// loop (not the shared task `Injector`, which is for ordinary spawns).
+    pub(crate) broadcast_queue: Arc<SegQueue<BroadcastJob>>,
+
+    /// Bitmask of CPUs this worker is pinned to (bit N = CPU N), or 0 if
+    /// unpinned. Mirrors whatever was last published to this worker's SCB
+    /// via `ScbHandle::set_cpu_mask`.
+    pub cpu_mask: u64,
+
     /// Thread join handle
     pub handle: Option<JoinHandle<()>>,
 }
@@ -74,6 +107,18 @@ pub struct WorkerConfig {
 
     /// Worker thread name prefix
     pub name_prefix: String,
+
+    /// How worker threads are pinned to CPUs (default: unpinned, the OS
+    /// scheduler decides). Pinning correlates a worker TID with a stable
+    /// CPU so the BPF side's `select_cpu`/`set_cpumask` path can cooperate
+    /// with cooperative scheduling instead of fighting CPU migration.
+    pub cpu_affinity: Affinity,
+
+    /// Explicit NUMA node layout override, one `CpuSet` per node - bypasses
+    /// `/sys/devices/system/node` detection under `Affinity::NumaAware`.
+    /// Set via `Builder::numa_topology`, primarily so tests can exercise
+    /// per-node injector sharding without a real multi-node machine.
+    pub numa_topology: Option<Vec<CpuSet>>,
 }
 
 impl Default for WorkerConfig {
@@ -84,6 +129,8 @@ impl Default for WorkerConfig {
                 .unwrap_or(1),
             escapable: true, // Rust default
             name_prefix: "morpheus-worker".to_string(),
+            cpu_affinity: Affinity::default(),
+            numa_topology: None,
         }
     }
 }
@@ -93,16 +140,145 @@ pub struct WorkerPool {
     workers: Vec<Worker>,
     config: WorkerConfig,
     shutdown: Arc<Mutex<bool>>,
+    /// Per-NUMA-node injector shards, shared by every worker's
+    /// `LocalExecutor`. Plain (non-`NumaAware`) placements get exactly one
+    /// shard, so sharding is invisible unless a caller opts in via
+    /// `Builder::cpu_affinity(Affinity::NumaAware)`.
+    injectors: Vec<Arc<Injector<Job>>>,
+    /// Round-robin cursor for `spawn()` calls made from outside any worker
+    /// thread, which have no node of their own to prefer.
+    next_injector: AtomicUsize,
 }
 
 impl WorkerPool {
     /// Create a new worker pool (workers not yet started)
     pub fn new(config: WorkerConfig) -> Self {
+        let num_nodes = match &config.cpu_affinity {
+            Affinity::NumaAware => affinity::numa_node_count(config.numa_topology.as_deref()),
+            _ => 1,
+        };
+
         Self {
             workers: Vec::with_capacity(config.num_workers),
             config,
             shutdown: Arc::new(Mutex::new(false)),
+            injectors: (0..num_nodes).map(|_| Arc::new(Injector::new())).collect(),
+            next_injector: AtomicUsize::new(0),
+        }
+    }
+
+    /// The shared per-node injector shards every worker's executor steals
+    /// batches from. Exposed so the code that actually spawns worker
+    /// threads can hand each `LocalExecutor` the same shards this pool
+    /// spawns onto, along with which shard is that worker's own.
+    pub(crate) fn injectors(&self) -> &[Arc<Injector<Job>>] {
+        &self.injectors
+    }
+
+    /// Which NUMA node worker `worker_index` should be assigned to, and
+    /// thus which shard in [`injectors`](Self::injectors) is its own.
+    pub(crate) fn worker_node(&self, worker_index: usize) -> usize {
+        affinity::worker_node(worker_index, self.injectors.len())
+    }
+
+    /// Spawn a future onto the pool.
+    ///
+    /// Pushes onto the calling thread's local deque if it's running one of
+    /// this pool's workers (for locality), otherwise round-robins across
+    /// the node injector shards for whichever worker steals it first.
+    pub fn spawn<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if let Some(executor) = executor::current_executor() {
+            return executor.spawn(future);
+        }
+
+        let injector = self.pick_injector().clone();
+        let schedule = {
+            let injector = injector.clone();
+            move |runnable: Runnable| {
+                injector.push(Job {
+                    runnable,
+                    affinity: None,
+                });
+            }
+        };
+        let (runnable, task) = async_task::spawn(future, schedule);
+        injector.push(Job {
+            runnable,
+            affinity: None,
+        });
+        task
+    }
+
+    /// Like `spawn`, but tags the task as preferring to keep running on
+    /// `worker_id`. See [`LocalExecutor::spawn_affine`] for what that
+    /// preference actually does.
+    pub fn spawn_affine<F>(&self, future: F, worker_id: u32) -> Task<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if let Some(executor) = executor::current_executor() {
+            return executor.spawn_affine(future, worker_id);
         }
+
+        let affinity = Some(worker_id);
+        let injector = self.injectors[self.worker_node(worker_id as usize)].clone();
+        let schedule = move |runnable: Runnable| {
+            injector.push(Job { runnable, affinity });
+        };
+        let (runnable, task) = async_task::spawn(future, schedule);
+        self.injectors[self.worker_node(worker_id as usize)].push(Job { runnable, affinity });
+        task
+    }
+
+    /// Next injector shard to use for a spawn with no worker context of its
+    /// own to prefer, round-robining across nodes so cross-thread spawns
+    /// don't all pile onto node 0's shard.
+    fn pick_injector(&self) -> &Arc<Injector<Job>> {
+        let idx = self.next_injector.fetch_add(1, Ordering::Relaxed) % self.injectors.len();
+        &self.injectors[idx]
+    }
+
+    /// Executor statistics for one worker, for the latency benchmark to
+    /// sample (queue depth, steal counts, yields).
+    pub fn worker_stats(&self, worker_id: u32) -> Option<Arc<ExecutorStats>> {
+        self.workers
+            .iter()
+            .find(|w| w.id == worker_id)
+            .map(|w| w.executor.stats().clone())
+    }
+
+    /// Current local queue depth for one worker.
+    pub fn worker_queue_depth(&self, worker_id: u32) -> Option<usize> {
+        self.workers
+            .iter()
+            .find(|w| w.id == worker_id)
+            .map(|w| w.executor.queue_depth())
+    }
+
+    /// The CPU bitmask one worker is pinned to (bit N = CPU N), or 0 if
+    /// unpinned, for the latency benchmark's `--pressure` runs to confirm
+    /// placement actually took effect.
+    pub fn worker_cpu_mask(&self, worker_id: u32) -> Option<u64> {
+        self.workers
+            .iter()
+            .find(|w| w.id == worker_id)
+            .map(|w| w.cpu_mask)
+    }
+
+    /// Scheduler-attributed cputime accounting for one worker (on/off-CPU
+    /// nanoseconds, vtime, voluntary yields, forced preempts), for the
+    /// latency benchmark to prove time actually spent off-CPU while
+    /// runnable, not just wall-clock per-op latency.
+    pub fn worker_cputime(&self, worker_id: u32) -> Option<crate::scb::WorkerStats> {
+        self.workers
+            .iter()
+            .find(|w| w.id == worker_id)
+            .map(|w| w.scb.cputime_stats())
     }
 
     /// Get the number of workers
@@ -130,6 +306,163 @@ impl WorkerPool {
     pub fn is_shutdown(&self) -> bool {
         *self.shutdown.lock()
     }
+
+    /// Get this worker's dedicated broadcast queue, for the run loop to
+    /// drain on each tick (checked ahead of ordinary task work, since
+    /// broadcast jobs are expected to be rare and latency-sensitive).
+    pub(crate) fn broadcast_queue(&self, worker_id: u32) -> Option<&Arc<SegQueue<BroadcastJob>>> {
+        self.workers
+            .iter()
+            .find(|w| w.id == worker_id)
+            .map(|w| &w.broadcast_queue)
+    }
+
+    /// Run `f` once on every worker thread, blocking until all copies have
+    /// completed and collecting each worker's result into a `Vec` indexed
+    /// by worker id.
+    ///
+    /// Unlike `spawn`, this does not go through the shared task `Injector`:
+    /// one job is pushed directly onto each worker's own `broadcast_queue`,
+    /// so every worker runs exactly one copy regardless of how busy the
+    /// others are. Useful for coordinated runtime-wide operations like
+    /// flipping every worker's `DefensiveMode` together, re-reading a
+    /// changed `runtime_priority`, or draining a per-worker thread-local
+    /// cache (results report what each worker drained).
+    ///
+    /// If the pool has no running workers yet, this returns an empty `Vec`
+    /// immediately.
+    pub fn broadcast<F, T>(&self, f: F) -> Vec<T>
+    where
+        F: Fn(BroadcastContext) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        if self.workers.is_empty() {
+            return Vec::new();
+        }
+
+        let f = Arc::new(f);
+        let results: Arc<Mutex<Vec<Option<T>>>> =
+            Arc::new(Mutex::new((0..self.workers.len()).map(|_| None).collect()));
+        let latch = Arc::new((Mutex::new(self.workers.len()), Condvar::new()));
+
+        for (idx, worker) in self.workers.iter().enumerate() {
+            let f = f.clone();
+            let latch = latch.clone();
+            let results = results.clone();
+            worker.broadcast_queue.push(Box::new(move |ctx| {
+                results.lock()[idx] = Some(f(ctx));
+                let (count, cvar) = &*latch;
+                let mut count = count.lock();
+                *count -= 1;
+                if *count == 0 {
+                    cvar.notify_all();
+                }
+            }));
+        }
+
+        let (count, cvar) = &*latch;
+        let mut count = count.lock();
+        while *count > 0 {
+            cvar.wait(&mut count);
+        }
+        drop(count);
+
+        collect_results(&results)
+    }
+
+    /// Async variant of [`broadcast`](Self::broadcast): pushes one job per
+    /// worker and returns a future that resolves, once every worker has run
+    /// its copy, to the same per-worker `Vec` `broadcast` would return -
+    /// without blocking the calling thread while it waits.
+    pub fn broadcast_async<F, T>(&self, f: F) -> BroadcastFuture<T>
+    where
+        F: Fn(BroadcastContext) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let remaining = Arc::new(AtomicUsize::new(self.workers.len()));
+        let waker = Arc::new(Mutex::new(None::<Waker>));
+        let results: Arc<Mutex<Vec<Option<T>>>> =
+            Arc::new(Mutex::new((0..self.workers.len()).map(|_| None).collect()));
+
+        if self.workers.is_empty() {
+            return BroadcastFuture {
+                remaining,
+                waker,
+                results,
+            };
+        }
+
+        let f = Arc::new(f);
+        for (idx, worker) in self.workers.iter().enumerate() {
+            let f = f.clone();
+            let remaining = remaining.clone();
+            let waker = waker.clone();
+            let results = results.clone();
+            worker.broadcast_queue.push(Box::new(move |ctx| {
+                results.lock()[idx] = Some(f(ctx));
+                if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                    if let Some(w) = waker.lock().take() {
+                        w.wake();
+                    }
+                }
+            }));
+        }
+
+        BroadcastFuture {
+            remaining,
+            waker,
+            results,
+        }
+    }
+}
+
+/// Drain `results` into a plain `Vec`, panicking if the latch released
+/// before every slot was actually filled in (which would mean the latch
+/// itself is broken, not a caller error).
+fn collect_results<T>(results: &Mutex<Vec<Option<T>>>) -> Vec<T> {
+    results
+        .lock()
+        .drain(..)
+        .map(|v| v.expect("every worker slot filled before latch releases"))
+        .collect()
+}
+
+/// Future returned by [`WorkerPool::broadcast_async`].
+pub struct BroadcastFuture<T> {
+    remaining: Arc<AtomicUsize>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    results: Arc<Mutex<Vec<Option<T>>>>,
+}
+
+impl<T> BroadcastFuture<T> {
+    /// A future that's already resolved, for broadcasting onto zero workers.
+    pub(crate) fn ready() -> Self {
+        Self {
+            remaining: Arc::new(AtomicUsize::new(0)),
+            waker: Arc::new(Mutex::new(None)),
+            results: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl<T> Future for BroadcastFuture<T> {
+    type Output = Vec<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<T>> {
+        if self.remaining.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(collect_results(&self.results));
+        }
+
+        *self.waker.lock() = Some(cx.waker().clone());
+
+        // Re-check after registering the waker to avoid missing a
+        // completion that raced between the load above and this point.
+        if self.remaining.load(Ordering::Acquire) == 0 {
+            Poll::Ready(collect_results(&self.results))
+        } else {
+            Poll::Pending
+        }
+    }
 }
 
 impl Drop for WorkerPool {
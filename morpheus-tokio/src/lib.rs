@@ -11,6 +11,26 @@
 //! - **Checkpoint macro**: Check for kernel yield requests in Tokio tasks
 //! - **Critical sections**: Protect FFI code from kernel escalation
 //! - **Yield hook**: Automatic yielding when kernel pressure is high
+//! - **Cooperative budget**: a per-task poll budget, seeded from the
+//!   current SCB's pressure level, forces a yield between checkpoints even
+//!   when the kernel itself hasn't asked for one (see [`checkpoint!`])
+//! - **Metrics**: opt-in counters for checkpoint hits, forced yields,
+//!   critical-section entries/exits, and an observed-pressure histogram
+//!   (see [`MorpheusTokioBuilder::enable_metrics`] and [`metrics()`])
+//! - **Tracing**: optional `tracing` spans/events (feature `tracing`) - a
+//!   span around each critical section with its pressure level and whether
+//!   the kernel's escalation request was deferred, and an event on every
+//!   forced yield with its [`YieldReason`] and the budget/pressure observed
+//!   at the time
+//! - **Cooperative cancellation**: [`MorpheusTokioBuilder::with_cancellation`]
+//!   wires a `tokio_util` `CancellationToken` to kernel-signaled escalation,
+//!   so [`checkpoint_or_cancel!`] can wind a long-running loop down cleanly
+//!   instead of forcing the kernel to escalate
+//! - **Pressure-reactive runtime**: [`MorpheusTokioBuilder::build`]
+//!   constructs a real `tokio::runtime::Runtime`, optionally sizing its
+//!   worker pool from the current SCB pressure level
+//!   ([`MorpheusTokioBuilder::worker_threads_auto`]) and re-seeding every
+//!   worker's cooperative budget on start and on park
 //!
 //! ## Usage
 //!
@@ -36,10 +56,7 @@ pub use morpheus_runtime::{
     ScbHandle, BpfMaps,
 };
 
-pub use morpheus_common::{
-    HintReason, MorpheusHint, MorpheusScb, GlobalPressure,
-    SchedulerMode, WorkerState, EscalationPolicy, YieldReason, RuntimeMode,
-};
+pub use morpheus_common::{HintReason, MorpheusHint, MorpheusScb};
 
 /// Check for pending kernel yield requests and yield to the Tokio runtime if needed.
 ///
@@ -64,8 +81,15 @@ pub use morpheus_common::{
 #[macro_export]
 macro_rules! checkpoint {
     () => {{
-        if $crate::checkpoint_sync() {
+        let __kernel_requested = $crate::checkpoint_sync();
+        let __budget_exhausted = $crate::poll_proceed();
+        let __should_yield = __kernel_requested || __budget_exhausted;
+        $crate::record_checkpoint_metrics(__should_yield);
+        if __should_yield {
+            $crate::trace_yield(__kernel_requested, __budget_exhausted);
+            let guard = $crate::RestoreOnYield::new();
             ::tokio::task::yield_now().await;
+            guard.disarm();
         }
     }};
 }
@@ -75,45 +99,571 @@ macro_rules! checkpoint {
 /// This is a more explicit version of checkpoint that always yields
 /// when kernel pressure is detected.
 pub async fn yield_if_requested() {
-    if checkpoint_sync() {
+    let kernel_requested = checkpoint_sync();
+    record_checkpoint_metrics(kernel_requested);
+    if kernel_requested {
+        trace_yield(kernel_requested, false);
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Base cooperative poll budget a task starts with, absent any pressure
+/// signal. Mirrors `morpheus_runtime::executor`'s own budget constant, so a
+/// Tokio-hosted task and a native Morpheus worker task force a yield at
+/// roughly the same cadence under no pressure.
+const BASE_BUDGET: u32 = 128;
+
+/// Floor the budget shrinks to under maximum kernel pressure (100). Never
+/// zero - a task under heavy pressure should still make *some* progress
+/// between yields, just a lot less.
+const MIN_BUDGET: u32 = 8;
+
+std::thread_local! {
+    /// Remaining cooperative poll budget for whatever task is currently
+    /// running `checkpoint!`/[`consume_budget`] on this thread. Reseeded
+    /// from the current pressure level each time it runs dry.
+    static POLL_BUDGET: std::cell::Cell<u32> = std::cell::Cell::new(BASE_BUDGET);
+}
+
+/// Derive a poll budget from the current SCB's `pressure_level()` (0-100):
+/// `BASE_BUDGET` under no pressure, shrinking linearly to `MIN_BUDGET` at
+/// maximum pressure. Falls back to `BASE_BUDGET` when not running on a
+/// Morpheus-managed worker (e.g. a plain Tokio runtime that hasn't
+/// connected to the kernel yet).
+fn seed_budget() -> u32 {
+    let Some(scb) = morpheus_runtime::worker::try_current_scb() else {
+        return BASE_BUDGET;
+    };
+    let pressure = scb.pressure_level().min(100);
+    BASE_BUDGET - (BASE_BUDGET - MIN_BUDGET) * pressure / 100
+}
+
+/// Consume one unit of the calling task's cooperative poll budget,
+/// reseeding it from the current pressure level first if it had run dry.
+/// Returns `true` once the budget is exhausted, meaning the caller should
+/// yield.
+///
+/// Not `#[doc(hidden)]`-worthy but not meant to be called directly either -
+/// exported only so `checkpoint!`'s expansion can reach it from other
+/// crates.
+pub fn poll_proceed() -> bool {
+    POLL_BUDGET.with(|budget| {
+        let remaining = match budget.get() {
+            0 => seed_budget(),
+            remaining => remaining,
+        };
+        let remaining = remaining.saturating_sub(1);
+        budget.set(remaining);
+        remaining == 0
+    })
+}
+
+/// Hand back the poll-budget unit [`poll_proceed`] consumed. Used by
+/// [`RestoreOnYield`] when a yield is cancelled before it completes, so the
+/// next poll doesn't start one unit short for work it never got to do.
+fn restore_budget() {
+    POLL_BUDGET.with(|budget| budget.set(budget.get().saturating_add(1)));
+}
+
+/// Guard held across a budget-exhausted `checkpoint!`/[`consume_budget`]
+/// yield. The consumed unit is only charged for good once the yield
+/// actually completes and the task resumes ([`disarm`](Self::disarm)); if
+/// the guard is dropped first - the task was cancelled mid-yield - the unit
+/// is restored instead, so budget is only ever spent on progress the task
+/// actually made.
+pub struct RestoreOnYield {
+    armed: bool,
+}
+
+impl RestoreOnYield {
+    /// Arm a new guard. Call right before the `yield_now().await` whose
+    /// budget unit it's tracking.
+    pub fn new() -> Self {
+        Self { armed: true }
+    }
+
+    /// Disarm the guard: the yield completed, so the consumed unit stands.
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Default for RestoreOnYield {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for RestoreOnYield {
+    fn drop(&mut self) {
+        if self.armed {
+            restore_budget();
+        }
+    }
+}
+
+/// Async equivalent of [`checkpoint!`] for code that isn't inside a tight
+/// loop: consumes one unit of the calling task's cooperative poll budget
+/// (in addition to the usual kernel-pressure check), yielding to the Tokio
+/// runtime if it's now exhausted.
+pub async fn consume_budget() {
+    let kernel_requested = checkpoint_sync();
+    let budget_exhausted = poll_proceed();
+    let should_yield = kernel_requested || budget_exhausted;
+    record_checkpoint_metrics(should_yield);
+    if should_yield {
+        trace_yield(kernel_requested, budget_exhausted);
+        let guard = RestoreOnYield::new();
         tokio::task::yield_now().await;
+        guard.disarm();
+    }
+}
+
+/// Why a `checkpoint!`/[`consume_budget`]/[`yield_if_requested`] call decided
+/// to yield.
+///
+/// Coarser than [`checkpoint_sync`]'s own decision: CPU-reclaim and
+/// chaos-injected yields are internal to `morpheus_runtime` (its `chaos` and
+/// `reclaim` modules only expose `pub(crate)` checks) and aren't
+/// distinguishable from out here, so both collapse into [`Other`](Self::Other).
+/// `KernelRequested` and `BudgetExhausted` are this crate's own checks and are
+/// always accurate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YieldReason {
+    /// The SCB's `yield_requested()` was set - the kernel asked directly.
+    KernelRequested,
+    /// This crate's own cooperative poll budget ran dry.
+    BudgetExhausted,
+    /// `checkpoint_sync()` returned `true` for some other, kernel-internal
+    /// reason (CPU reclaim or a chaos-mode injected yield).
+    Other,
+}
+
+/// Classify why a yield is about to happen, from the two signals this crate
+/// can actually observe: whether the kernel asked directly, and whether our
+/// own budget ran out.
+fn classify_yield(kernel_requested: bool, budget_exhausted: bool) -> YieldReason {
+    if kernel_requested {
+        YieldReason::KernelRequested
+    } else if budget_exhausted {
+        YieldReason::BudgetExhausted
+    } else {
+        YieldReason::Other
+    }
+}
+
+/// Emit a `tracing` event for a yield about to happen, carrying its
+/// [`YieldReason`] plus the pressure and remaining budget observed at the
+/// moment of the decision. Compiles to nothing without the `tracing` feature.
+///
+/// Exported only so `checkpoint!`'s expansion can reach it from other
+/// crates - not meant to be called directly.
+#[cfg(feature = "tracing")]
+pub fn trace_yield(kernel_requested: bool, budget_exhausted: bool) {
+    let scb = morpheus_runtime::worker::try_current_scb();
+    // `kernel_requested` is `checkpoint_sync()`'s result, which is also true
+    // for CPU reclaim and chaos; only the SCB's own flag means the kernel
+    // asked directly.
+    let scb_requested = kernel_requested && scb.as_ref().is_some_and(|scb| scb.yield_requested());
+    let reason = classify_yield(scb_requested, budget_exhausted);
+    let pressure = scb.map(|scb| scb.pressure_level());
+    let budget_remaining = POLL_BUDGET.with(|budget| budget.get());
+    tracing::event!(
+        tracing::Level::DEBUG,
+        ?reason,
+        ?pressure,
+        budget_remaining,
+        "morpheus checkpoint forced a yield"
+    );
+}
+
+/// No-op without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub fn trace_yield(_kernel_requested: bool, _budget_exhausted: bool) {}
+
+/// Shared cancellation token installed via
+/// [`MorpheusTokioBuilder::with_cancellation`], if any. [`checkpoint_or_cancel!`]
+/// fires it and checks it on every call.
+static CANCELLATION_TOKEN: std::sync::RwLock<Option<tokio_util::sync::CancellationToken>> =
+    std::sync::RwLock::new(None);
+
+/// The process-wide cancellation token installed via
+/// [`MorpheusTokioBuilder::with_cancellation`], if any. Cloned out rather
+/// than returning a guard - a `CancellationToken` is just a cheap
+/// `Arc`-backed handle.
+///
+/// Exported only so `checkpoint_or_cancel!`'s expansion can reach it from
+/// other crates - not meant to be called directly.
+pub fn cancellation_token() -> Option<tokio_util::sync::CancellationToken> {
+    CANCELLATION_TOKEN.read().unwrap().clone()
+}
+
+/// True if the current worker's SCB is signaling an escalation this task
+/// has opted into via `escapable`: the kernel has an outstanding yield
+/// request (`yield_requested()`) *and* this worker allows forced
+/// escalation, meaning the kernel might otherwise resort to preempting it
+/// outright instead of waiting for a cooperative checkpoint. Always `false`
+/// off a Morpheus worker thread (no SCB to read).
+///
+/// Exported only so `checkpoint_or_cancel!`'s expansion can reach it from
+/// other crates - not meant to be called directly.
+pub fn escalation_requested() -> bool {
+    morpheus_runtime::worker::try_current_scb()
+        .map(|scb| {
+            scb.yield_requested()
+                && scb.scb().escapable.load(std::sync::atomic::Ordering::Relaxed) == 1
+        })
+        .unwrap_or(false)
+}
+
+/// Like [`checkpoint!`], but also drives a
+/// [`CancellationToken`](tokio_util::sync::CancellationToken) installed via
+/// [`MorpheusTokioBuilder::with_cancellation`]: fires it the first time the
+/// SCB signals an escalation this task opted into ([`escalation_requested`]),
+/// then returns early from the enclosing function once the token is
+/// tripped - by this or any other source - so a long-running loop can drain
+/// on its own terms instead of being force-preempted.
+///
+/// No-op beyond a plain [`checkpoint!`] if no token has been installed.
+/// Only meant for loops inside functions returning `()`: unlike
+/// [`checkpoint!`], it expands to an early `return` in the caller's scope.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use morpheus_tokio::checkpoint_or_cancel;
+///
+/// async fn heavy_computation() {
+///     for i in 0..1_000_000 {
+///         if i % 1000 == 0 {
+///             checkpoint_or_cancel!();
+///         }
+///         // ... compute ...
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! checkpoint_or_cancel {
+    () => {{
+        $crate::checkpoint!();
+        if let Some(__token) = $crate::cancellation_token() {
+            if $crate::escalation_requested() {
+                __token.cancel();
+            }
+            if __token.is_cancelled() {
+                return;
+            }
+        }
+    }};
+}
+
+/// Number of buckets [`MetricsSnapshot::pressure_histogram`] sorts observed
+/// `pressure_level()` readings into - ten 10-wide buckets spanning the SCB's
+/// 0-100 range (`[0]` = 0-9, ..., `[9]` = 90-100).
+const PRESSURE_BUCKETS: usize = 10;
+
+/// Lock-free counters for how often kernel-guided scheduling actually fires
+/// from a Tokio task's perspective: checkpoint hits and the forced yields
+/// they triggered, critical-section entries/exits, and a histogram of the
+/// kernel pressure level observed at each checkpoint.
+///
+/// Disabled by default ([`MorpheusTokioBuilder::enable_metrics`]) since
+/// these counters are recorded on this crate's hottest paths - every
+/// `checkpoint!` and every `critical_section()` call.
+struct MorpheusMetrics {
+    checkpoint_hits: std::sync::atomic::AtomicU64,
+    forced_yields: std::sync::atomic::AtomicU64,
+    critical_enters: std::sync::atomic::AtomicU64,
+    critical_exits: std::sync::atomic::AtomicU64,
+    pressure_histogram: [std::sync::atomic::AtomicU64; PRESSURE_BUCKETS],
+}
+
+impl MorpheusMetrics {
+    fn new() -> Self {
+        Self {
+            checkpoint_hits: std::sync::atomic::AtomicU64::new(0),
+            forced_yields: std::sync::atomic::AtomicU64::new(0),
+            critical_enters: std::sync::atomic::AtomicU64::new(0),
+            critical_exits: std::sync::atomic::AtomicU64::new(0),
+            pressure_histogram: std::array::from_fn(|_| std::sync::atomic::AtomicU64::new(0)),
+        }
+    }
+
+    fn record_checkpoint(&self, forced_yield: bool) {
+        self.checkpoint_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if forced_yield {
+            self.forced_yields.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    fn record_critical_enter(&self) {
+        self.critical_enters.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn record_critical_exit(&self) {
+        self.critical_exits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn record_pressure(&self, level: u32) {
+        let bucket = (level.min(99) / 10) as usize;
+        self.pressure_histogram[bucket].fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> MetricsSnapshot {
+        use std::sync::atomic::Ordering::Relaxed;
+        MetricsSnapshot {
+            checkpoint_hits: self.checkpoint_hits.load(Relaxed),
+            forced_yields: self.forced_yields.load(Relaxed),
+            critical_enters: self.critical_enters.load(Relaxed),
+            critical_exits: self.critical_exits.load(Relaxed),
+            pressure_histogram: std::array::from_fn(|i| self.pressure_histogram[i].load(Relaxed)),
+        }
     }
 }
 
+/// Snapshot of [`MorpheusMetrics`]' counters, returned by [`metrics()`].
+/// Each counter is loaded on its own, so the fields are not read at one
+/// single instant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Total `checkpoint!`/`consume_budget`/`yield_if_requested` calls.
+    pub checkpoint_hits: u64,
+    /// Of `checkpoint_hits`, how many actually forced a yield (kernel
+    /// request, chaos, reclaim, or cooperative budget exhaustion).
+    pub forced_yields: u64,
+    /// Total critical-section entries (outermost `critical_section()` calls).
+    pub critical_enters: u64,
+    /// Total critical-section exits (outermost `CriticalGuard` drops).
+    pub critical_exits: u64,
+    /// Observed `pressure_level()` readings, bucketed into ten 10-wide
+    /// buckets spanning 0-100.
+    pub pressure_histogram: [u64; PRESSURE_BUCKETS],
+}
+
+static METRICS_ENABLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+static METRICS: std::sync::OnceLock<MorpheusMetrics> = std::sync::OnceLock::new();
+
+fn metrics_instance() -> &'static MorpheusMetrics {
+    METRICS.get_or_init(MorpheusMetrics::new)
+}
+
+fn metrics_enabled() -> bool {
+    METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Record one `checkpoint!`/[`consume_budget`]/[`yield_if_requested`] hit
+/// and, if metrics are enabled, the pressure level it observed.
+///
+/// Exported only so `checkpoint!`'s expansion can reach it from other
+/// crates - not meant to be called directly.
+pub fn record_checkpoint_metrics(forced_yield: bool) {
+    if !metrics_enabled() {
+        return;
+    }
+    metrics_instance().record_checkpoint(forced_yield);
+    if let Some(scb) = morpheus_runtime::worker::try_current_scb() {
+        metrics_instance().record_pressure(scb.pressure_level());
+    }
+}
+
+/// Span entered for the duration of the current thread's outermost
+/// critical section, so [`InstrumentedCriticalBackend::exit`] can record its
+/// `escalation_deferred` field and close it. Only ever touched from the
+/// thread that opened it - critical sections are `!Send`.
+#[cfg(feature = "tracing")]
+std::thread_local! {
+    static CRITICAL_SPAN: std::cell::RefCell<Option<tracing::span::EnteredSpan>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// [`CriticalBackend`](morpheus_runtime::CriticalBackend) that records
+/// critical-section entries/exits into [`MorpheusMetrics`] (gated on
+/// [`metrics_enabled`]) and, under the `tracing` feature, opens a span for
+/// the critical section's duration - before forwarding to the same SCB
+/// behavior the default backend provides.
+struct InstrumentedCriticalBackend;
+
+impl morpheus_runtime::CriticalBackend for InstrumentedCriticalBackend {
+    fn enter(&self) {
+        if metrics_enabled() {
+            metrics_instance().record_critical_enter();
+        }
+        trace_critical_enter();
+        if let Some(scb) = morpheus_runtime::worker::try_current_scb() {
+            scb.enter_critical();
+        }
+    }
+
+    fn exit(&self) {
+        if metrics_enabled() {
+            metrics_instance().record_critical_exit();
+        }
+        trace_critical_exit();
+        if let Some(scb) = morpheus_runtime::worker::try_current_scb() {
+            scb.exit_critical();
+        }
+    }
+}
+
+/// Open a debug span for a critical section that just started, tagged with
+/// the pressure level observed on entry. Stashed in [`CRITICAL_SPAN`] so
+/// [`trace_critical_exit`] can record `escalation_deferred` and close it.
+/// No-op without the `tracing` feature.
+#[cfg(feature = "tracing")]
+fn trace_critical_enter() {
+    let pressure = morpheus_runtime::worker::try_current_scb().map(|scb| scb.pressure_level());
+    let span = tracing::debug_span!(
+        "morpheus_critical_section",
+        ?pressure,
+        escalation_deferred = tracing::field::Empty,
+    );
+    CRITICAL_SPAN.with(|cell| *cell.borrow_mut() = Some(span.entered()));
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_critical_enter() {}
+
+/// Record whether the kernel asked this critical section to yield while it
+/// was held - a request `critical_section()` defers until the matching
+/// `CriticalGuard` drops - then close the span opened by
+/// [`trace_critical_enter`]. No-op without the `tracing` feature.
+#[cfg(feature = "tracing")]
+fn trace_critical_exit() {
+    let deferred = morpheus_runtime::worker::try_current_scb()
+        .map(|scb| scb.yield_requested())
+        .unwrap_or(false);
+    CRITICAL_SPAN.with(|cell| {
+        if let Some(span) = cell.borrow_mut().take() {
+            span.record("escalation_deferred", deferred);
+        }
+    });
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_critical_exit() {}
+
+/// Install [`InstrumentedCriticalBackend`] as the process-wide
+/// [`CriticalBackend`](morpheus_runtime::CriticalBackend), if it hasn't been
+/// already. Idempotent and cheap to call from multiple entry points
+/// ([`set_metrics_enabled`], [`MorpheusTokioBuilder::new`]) since whichever
+/// runs first wins and the rest are no-ops.
+fn ensure_critical_backend_installed() {
+    static INSTALLED: std::sync::Once = std::sync::Once::new();
+    INSTALLED.call_once(|| {
+        morpheus_runtime::set_critical_backend(InstrumentedCriticalBackend);
+    });
+}
+
+/// Enable or disable the metrics counters process-wide. Disabled by
+/// default. Enabling installs [`InstrumentedCriticalBackend`] (if not
+/// already installed) so critical-section entries/exits get counted
+/// alongside the default SCB behavior; once installed it stays installed
+/// even if metrics are later disabled - disabling just stops the counters
+/// from advancing.
+fn set_metrics_enabled(enabled: bool) {
+    if enabled {
+        ensure_critical_backend_installed();
+    }
+    METRICS_ENABLED.store(enabled, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Current metrics snapshot, or `None` if metrics collection hasn't been
+/// enabled via [`MorpheusTokioBuilder::enable_metrics`].
+pub fn metrics() -> Option<MetricsSnapshot> {
+    metrics_enabled().then(|| metrics_instance().snapshot())
+}
+
+/// [`with_checkpoints`]'s check cadence, set via
+/// [`MorpheusTokioBuilder::check_interval_ms`]. Matches the builder's own
+/// default (1ms) until overridden.
+static CHECK_INTERVAL_MS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// The interval [`with_checkpoints`] checks `checkpoint_sync()` at, per the
+/// last [`MorpheusTokioBuilder::check_interval_ms`] call.
+fn configured_check_interval() -> std::time::Duration {
+    std::time::Duration::from_millis(CHECK_INTERVAL_MS.load(std::sync::atomic::Ordering::Relaxed))
+}
+
 /// Run a future with Morpheus kernel-guided scheduling.
 ///
-/// This wrapper periodically checks for kernel yield requests.
-/// Use this when you can't add explicit checkpoints.
-pub async fn with_checkpoints<F, T>(future: F, check_interval: std::time::Duration) -> T
+/// This wrapper checks for kernel yield requests once per
+/// [`MorpheusTokioBuilder::check_interval_ms`] tick rather than on every
+/// poll. When a check lands on a pending kernel request, it drives a real
+/// [`tokio::task::yield_now`] to completion - re-queuing the task at the
+/// back of the runtime's run queue and letting the scheduler actually park
+/// it - before the inner future is polled again. Use this when you can't
+/// add explicit checkpoints. Each forced yield goes through the same
+/// [`record_checkpoint_metrics`]/[`trace_yield`] path as `checkpoint!`, so
+/// this future's stalls show up in [`metrics()`] and `tracing` output the
+/// same way explicit checkpoints do.
+pub async fn with_checkpoints<F, T>(future: F) -> T
 where
     F: std::future::Future<Output = T>,
 {
     use std::pin::pin;
-    use std::task::{Context, Poll};
-    
+
     let mut future = pin!(future);
-    let mut interval = tokio::time::interval(check_interval);
-    
-    std::future::poll_fn(|cx: &mut Context<'_>| {
-        // Check for kernel yield
-        if checkpoint_sync() {
-            // Wake ourselves to yield
-            cx.waker().wake_by_ref();
-            return Poll::Pending;
+    let mut interval = tokio::time::interval(configured_check_interval());
+
+    loop {
+        tokio::select! {
+            output = &mut future => return output,
+            _ = interval.tick() => {
+                let kernel_requested = checkpoint_sync();
+                record_checkpoint_metrics(kernel_requested);
+                if kernel_requested {
+                    trace_yield(kernel_requested, false);
+                    tokio::task::yield_now().await;
+                }
+            }
         }
-        
-        // Try to advance the interval
-        let _ = interval.poll_tick(cx);
-        
-        // Poll the inner future
-        future.as_mut().poll(cx)
-    }).await
+    }
+}
+
+/// Worker-thread count for [`MorpheusTokioBuilder::worker_threads_auto`],
+/// scaled down from `available_parallelism()` by the current pressure
+/// level: a throttled process asks the kernel for fewer threads it likely
+/// couldn't get scheduled promptly anyway. Falls back to plain
+/// `available_parallelism()` when run off a Morpheus worker thread (no SCB
+/// to read a pressure level from).
+fn auto_worker_threads() -> usize {
+    let available = std::thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1);
+    let Some(scb) = morpheus_runtime::worker::try_current_scb() else {
+        return available;
+    };
+    let pressure = scb.pressure_level().min(100) as usize;
+    (available * (100 - pressure) / 100).max(1)
+}
+
+/// Whether the current pressure level is high enough that spreading tasks
+/// across the global run queue - rather than favoring each worker's LIFO
+/// slot for throughput - better serves fairness under load. `false` (favor
+/// the LIFO slot, Tokio's default) off a Morpheus worker thread.
+fn pressure_favors_global_queue() -> bool {
+    morpheus_runtime::worker::try_current_scb()
+        .map(|scb| scb.pressure_level() >= 50)
+        .unwrap_or(false)
+}
+
+/// `on_thread_start`/`on_thread_park` hook installed by
+/// [`MorpheusTokioBuilder::build`]: re-seeds the calling worker thread's
+/// cooperative poll budget from the pressure level observed right now, so a
+/// worker that parked under low pressure and wakes up under high pressure
+/// (or vice versa) doesn't keep running on a stale budget until it happens
+/// to exhaust it.
+fn reseed_worker_budget() {
+    POLL_BUDGET.with(|budget| budget.set(seed_budget()));
 }
 
 /// Builder for configuring Morpheus with Tokio.
 pub struct MorpheusTokioBuilder {
     escapable: bool,
     check_interval_ms: u64,
+    metrics_enabled: bool,
+    cancellation: Option<tokio_util::sync::CancellationToken>,
+    worker_threads_auto: bool,
 }
 
 impl Default for MorpheusTokioBuilder {
@@ -121,6 +671,9 @@ impl Default for MorpheusTokioBuilder {
         Self {
             escapable: true, // Rust default
             check_interval_ms: 1,
+            metrics_enabled: false,
+            cancellation: None,
+            worker_threads_auto: false,
         }
     }
 }
@@ -128,6 +681,11 @@ impl Default for MorpheusTokioBuilder {
 impl MorpheusTokioBuilder {
     /// Create a new builder with default configuration.
     pub fn new() -> Self {
+        // Under the `tracing` feature, critical sections should always get
+        // their span, whether or not metrics end up enabled - install the
+        // backend here rather than waiting on `enable_metrics`.
+        #[cfg(feature = "tracing")]
+        ensure_critical_backend_installed();
         Self::default()
     }
 
@@ -141,9 +699,12 @@ impl MorpheusTokioBuilder {
 
     /// Set the check interval in milliseconds.
     ///
-    /// Lower values = more responsive but higher overhead.
+    /// Lower values = more responsive but higher overhead. This is the
+    /// single source of truth for [`with_checkpoints`]'s check cadence, so
+    /// it takes effect immediately rather than waiting for a `build()`.
     pub fn check_interval_ms(mut self, ms: u64) -> Self {
         self.check_interval_ms = ms;
+        CHECK_INTERVAL_MS.store(ms, std::sync::atomic::Ordering::Relaxed);
         self
     }
 
@@ -156,11 +717,86 @@ impl MorpheusTokioBuilder {
     pub fn get_check_interval(&self) -> std::time::Duration {
         std::time::Duration::from_millis(self.check_interval_ms)
     }
+
+    /// Enable or disable the `checkpoint!`/critical-section metrics counters
+    /// surfaced by [`metrics()`]. Disabled by default, since the counters
+    /// are recorded on this crate's hottest paths. Takes effect immediately
+    /// rather than waiting for a `build()`, so operators can flip it on
+    /// mid-process (e.g. from a debug endpoint).
+    pub fn enable_metrics(mut self, enabled: bool) -> Self {
+        self.metrics_enabled = enabled;
+        set_metrics_enabled(enabled);
+        self
+    }
+
+    /// Get the metrics-enabled setting.
+    pub fn is_metrics_enabled(&self) -> bool {
+        self.metrics_enabled
+    }
+
+    /// Install a `CancellationToken` for [`checkpoint_or_cancel!`] to drive:
+    /// fired automatically the first time the kernel signals an escalation
+    /// this (escapable) task opted into, and checked on every
+    /// `checkpoint_or_cancel!` call so `cancelled().await` branches and
+    /// `select!` arms elsewhere in the task wind down in step. Takes effect
+    /// immediately, like [`enable_metrics`](Self::enable_metrics).
+    pub fn with_cancellation(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        *CANCELLATION_TOKEN.write().unwrap() = Some(token.clone());
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Get the installed cancellation token, if any.
+    pub fn cancellation(&self) -> Option<&tokio_util::sync::CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    /// Opt into sizing [`build`](Self::build)'s worker pool from the
+    /// kernel's current view of system load instead of a static
+    /// `worker_threads(N)`. See [`auto_worker_threads`] for how the count is
+    /// derived.
+    pub fn worker_threads_auto(mut self) -> Self {
+        self.worker_threads_auto = true;
+        self
+    }
+
+    /// Get the worker-threads-auto setting.
+    pub fn is_worker_threads_auto(&self) -> bool {
+        self.worker_threads_auto
+    }
+
+    /// Build a multi-threaded Tokio runtime configured from this builder.
+    ///
+    /// If [`worker_threads_auto`](Self::worker_threads_auto) was set, the
+    /// worker count comes from [`auto_worker_threads`] instead of Tokio's
+    /// own `available_parallelism()` default; the LIFO slot is disabled in
+    /// favor of the global queue under high pressure
+    /// ([`pressure_favors_global_queue`]); and every worker thread gets its
+    /// cooperative poll budget re-seeded from the current pressure level on
+    /// start and on every park, via [`reseed_worker_budget`].
+    pub fn build(self) -> Result<tokio::runtime::Runtime> {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder.enable_all();
+
+        if self.worker_threads_auto {
+            builder.worker_threads(auto_worker_threads());
+        }
+        if pressure_favors_global_queue() {
+            builder.disable_lifo_slot();
+        }
+
+        builder
+            .on_thread_start(reseed_worker_budget)
+            .on_thread_park(reseed_worker_budget)
+            .build()
+            .map_err(|err| morpheus_runtime::Error::Build(err.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serial_test::serial;
 
     #[tokio::test]
     async fn test_yield_if_requested() {
@@ -183,4 +819,189 @@ mod tests {
         // Should return false when no kernel connected
         assert!(!checkpoint_sync());
     }
+
+    #[test]
+    fn test_seed_budget_falls_back_without_a_worker() {
+        // Not running on a Morpheus worker thread, so no SCB to read a
+        // pressure level from.
+        assert_eq!(seed_budget(), BASE_BUDGET);
+    }
+
+    #[tokio::test]
+    async fn test_poll_proceed_exhausts_after_base_budget_polls() {
+        POLL_BUDGET.with(|budget| budget.set(BASE_BUDGET));
+
+        for _ in 0..BASE_BUDGET - 1 {
+            assert!(!poll_proceed());
+        }
+        assert!(poll_proceed());
+    }
+
+    #[tokio::test]
+    async fn test_restore_on_yield_restores_when_dropped_still_armed() {
+        POLL_BUDGET.with(|budget| budget.set(1));
+        assert!(poll_proceed());
+
+        drop(RestoreOnYield::new());
+
+        assert_eq!(POLL_BUDGET.with(|budget| budget.get()), 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_on_yield_leaves_budget_alone_when_disarmed() {
+        POLL_BUDGET.with(|budget| budget.set(1));
+        assert!(poll_proceed());
+
+        RestoreOnYield::new().disarm();
+
+        assert_eq!(POLL_BUDGET.with(|budget| budget.get()), 0);
+    }
+
+    #[test]
+    fn test_builder_enable_metrics_round_trips() {
+        let builder = MorpheusTokioBuilder::new().enable_metrics(true);
+        assert!(builder.is_metrics_enabled());
+    }
+
+    #[test]
+    fn test_metrics_snapshot_counts_hits_and_forced_yields() {
+        let m = MorpheusMetrics::new();
+        m.record_checkpoint(false);
+        m.record_checkpoint(true);
+        m.record_checkpoint(true);
+
+        let snapshot = m.snapshot();
+        assert_eq!(snapshot.checkpoint_hits, 3);
+        assert_eq!(snapshot.forced_yields, 2);
+    }
+
+    #[test]
+    fn test_metrics_snapshot_counts_critical_enters_and_exits() {
+        let m = MorpheusMetrics::new();
+        m.record_critical_enter();
+        m.record_critical_enter();
+        m.record_critical_exit();
+
+        let snapshot = m.snapshot();
+        assert_eq!(snapshot.critical_enters, 2);
+        assert_eq!(snapshot.critical_exits, 1);
+    }
+
+    #[test]
+    fn test_pressure_histogram_buckets_by_ten() {
+        let m = MorpheusMetrics::new();
+        m.record_pressure(0);
+        m.record_pressure(9);
+        m.record_pressure(55);
+        m.record_pressure(100); // clamped into the top bucket
+
+        let snapshot = m.snapshot();
+        assert_eq!(snapshot.pressure_histogram[0], 2);
+        assert_eq!(snapshot.pressure_histogram[5], 1);
+        assert_eq!(snapshot.pressure_histogram[9], 1);
+        assert_eq!(snapshot.pressure_histogram.iter().sum::<u64>(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_consume_budget_yields_once_exhausted() {
+        POLL_BUDGET.with(|budget| budget.set(1));
+        // Exhausts the budget and yields back to the runtime; should not
+        // panic or hang. The yield completes normally, so the guard
+        // disarms and the consumed unit stays spent.
+        consume_budget().await;
+        assert_eq!(POLL_BUDGET.with(|budget| budget.get()), 0);
+    }
+
+    #[test]
+    fn test_classify_yield_prefers_kernel_requested() {
+        assert_eq!(
+            classify_yield(true, true),
+            YieldReason::KernelRequested
+        );
+    }
+
+    #[test]
+    fn test_classify_yield_falls_back_to_budget_exhausted() {
+        assert_eq!(classify_yield(false, true), YieldReason::BudgetExhausted);
+    }
+
+    #[test]
+    fn test_classify_yield_other_when_neither_signal_set() {
+        // checkpoint_sync() returned true for a reason morpheus_runtime
+        // doesn't surface past its own boundary (reclaim, chaos).
+        assert_eq!(classify_yield(false, false), YieldReason::Other);
+    }
+
+    #[test]
+    fn test_escalation_requested_false_without_a_worker() {
+        // Not running on a Morpheus worker thread, so no SCB to read.
+        assert!(!escalation_requested());
+    }
+
+    // CANCELLATION_TOKEN is a single process-wide static that both of
+    // these tests install and then read back; running them concurrently
+    // (cargo test's default) lets one test observe the other's token.
+    // Serialize just the two against each other.
+
+    #[test]
+    #[serial(cancellation_token)]
+    fn test_builder_with_cancellation_round_trips() {
+        let token = tokio_util::sync::CancellationToken::new();
+        let builder = MorpheusTokioBuilder::new().with_cancellation(token.clone());
+
+        assert!(builder.cancellation().is_some());
+        assert_eq!(cancellation_token().unwrap().is_cancelled(), token.is_cancelled());
+    }
+
+    #[tokio::test]
+    #[serial(cancellation_token)]
+    async fn test_checkpoint_or_cancel_returns_early_once_token_is_cancelled() {
+        let token = tokio_util::sync::CancellationToken::new();
+        MorpheusTokioBuilder::new().with_cancellation(token.clone());
+        token.cancel();
+
+        async fn drain() {
+            checkpoint_or_cancel!();
+            panic!("should have returned before reaching here");
+        }
+
+        drain().await;
+    }
+
+    #[tokio::test]
+    async fn test_with_checkpoints_returns_inner_future_output() {
+        let output = with_checkpoints(async { 42 }).await;
+        assert_eq!(output, 42);
+    }
+
+    #[test]
+    fn test_auto_worker_threads_falls_back_to_available_parallelism() {
+        // Not running on a Morpheus worker thread, so no pressure to scale
+        // down by.
+        let available = std::thread::available_parallelism()
+            .map(std::num::NonZeroUsize::get)
+            .unwrap_or(1);
+        assert_eq!(auto_worker_threads(), available);
+    }
+
+    #[test]
+    fn test_pressure_favors_global_queue_false_without_a_worker() {
+        assert!(!pressure_favors_global_queue());
+    }
+
+    #[test]
+    fn test_builder_worker_threads_auto_round_trips() {
+        let builder = MorpheusTokioBuilder::new().worker_threads_auto();
+        assert!(builder.is_worker_threads_auto());
+    }
+
+    #[test]
+    fn test_builder_build_produces_a_working_runtime() {
+        let runtime = MorpheusTokioBuilder::new()
+            .worker_threads_auto()
+            .build()
+            .expect("runtime should build");
+
+        assert_eq!(runtime.block_on(async { 7 }), 7);
+    }
 }
@@ -10,17 +10,29 @@ mod bpf {
     include!(concat!(env!("OUT_DIR"), "/scx_morpheus.skel.rs"));
 }
 
+mod telemetry;
+
 use anyhow::{Context, Result};
 use clap::Parser;
 use libbpf_rs::skel::{OpenSkel, Skel, SkelBuilder};
+use morpheus_common::{MorpheusConfig, MorpheusHint, MorpheusStats};
+use signal_hook::consts::SIGHUP;
+use signal_hook::iterator::Signals;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use telemetry::TelemetryTarget;
 use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use bpf::*;
 
+/// Default directory under which `worker_tid_map` and `scb_map` are pinned
+/// when `--pin-maps` is passed. Must match the path `BpfMaps::from_pinned_paths`
+/// expects out-of-process workers to use.
+const DEFAULT_PIN_DIR: &str = "/sys/fs/bpf/morpheus";
+
 /// Morpheus-Hybrid sched_ext scheduler
 ///
 /// A kernel-guided cooperative async runtime scheduler that emits yield
@@ -44,6 +56,25 @@ struct Args {
     /// Print stats every N seconds (0 to disable)
     #[arg(long, default_value_t = 5)]
     stats_interval: u64,
+
+    /// Pin worker_tid_map and scb_map so out-of-process workers can attach
+    /// via `BpfMaps::from_pinned_paths`
+    #[arg(long)]
+    pin_maps: bool,
+
+    /// Directory to pin maps under (only used with --pin-maps)
+    #[arg(long, default_value = DEFAULT_PIN_DIR)]
+    pin_dir: PathBuf,
+
+    /// Config file re-read on SIGHUP to retune slice/grace period live.
+    /// Lines are `key=value` pairs: slice_ms, grace_ms, debug.
+    #[arg(long)]
+    config_file: Option<PathBuf>,
+
+    /// Stream hint events live to this socket: a filesystem path for a Unix
+    /// domain socket, or `tcp://host:port` for TCP with TCP_NODELAY set.
+    #[arg(long)]
+    telemetry_socket: Option<TelemetryTarget>,
 }
 
 fn main() -> Result<()> {
@@ -66,20 +97,37 @@ fn main() -> Result<()> {
 
     // Build and load BPF skeleton
     let skel_builder = ScxMorpheusSkelBuilder::default();
-    let mut open_skel = skel_builder.open().context("Failed to open BPF skeleton")?;
-
-    // Set configuration before loading
-    open_skel.rodata_mut().slice_ns = args.slice_ms * 1_000_000;
-    open_skel.rodata_mut().grace_period_ns = args.grace_ms * 1_000_000;
-    open_skel.rodata_mut().debug_mode = args.debug;
-
+    let open_skel = skel_builder.open().context("Failed to open BPF skeleton")?;
     let mut skel = open_skel.load().context("Failed to load BPF program")?;
 
+    // slice_ns, grace_period_ns and debug_mode now live in config_map, a
+    // one-element BPF_MAP_TYPE_ARRAY the BPF side re-reads every tick, rather
+    // than rodata baked in at load time. This lets us retune them below
+    // without tearing down the scheduler.
+    let config = MorpheusConfig::new(args.slice_ms, args.grace_ms, args.debug);
+    write_config(&skel, &config).context("Failed to write initial config_map entry")?;
+
     // Attach the scheduler
     skel.attach().context("Failed to attach sched_ext ops")?;
 
     info!("scx_morpheus attached successfully");
 
+    if args.pin_maps {
+        pin_maps(&skel, &args.pin_dir).context("Failed to pin BPF maps")?;
+        info!("pinned worker_tid_map and scb_map under {:?}", args.pin_dir);
+    }
+
+    if let Some(target) = args.telemetry_socket.clone() {
+        let flush_interval = if args.stats_interval > 0 {
+            Duration::from_secs(args.stats_interval)
+        } else {
+            Duration::from_secs(1)
+        };
+        let telemetry = telemetry::spawn(target, flush_interval)
+            .context("Failed to start telemetry streaming")?;
+        spawn_hint_drain(&skel, telemetry).context("Failed to start hint ring buffer drain")?;
+    }
+
     // Set up graceful shutdown
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
@@ -89,6 +137,21 @@ fn main() -> Result<()> {
     })
     .context("Error setting Ctrl-C handler")?;
 
+    // SIGHUP re-reads --config-file; the signal thread only parses the file
+    // and hands the result to the main loop over a channel, since config_map
+    // writes go through `skel`, which stays owned by this thread.
+    let (reload_tx, reload_rx) = std::sync::mpsc::channel::<PathBuf>();
+    if let Some(config_file) = args.config_file.clone() {
+        let mut signals = Signals::new([SIGHUP]).context("Error setting SIGHUP handler")?;
+        std::thread::spawn(move || {
+            for _ in signals.forever() {
+                if reload_tx.send(config_file.clone()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
     // Main loop: print stats periodically
     let stats_interval = if args.stats_interval > 0 {
         Some(Duration::from_secs(args.stats_interval))
@@ -97,6 +160,16 @@ fn main() -> Result<()> {
     };
 
     while running.load(Ordering::SeqCst) {
+        while let Ok(config_file) = reload_rx.try_recv() {
+            match reload_config(&config_file) {
+                Ok(config) => match write_config(&skel, &config) {
+                    Ok(()) => info!("reloaded config from {:?}: {:?}", config_file, config),
+                    Err(e) => error!("failed to apply reloaded config: {}", e),
+                },
+                Err(e) => error!("failed to reload config from {:?}: {}", config_file, e),
+            }
+        }
+
         if let Some(interval) = stats_interval {
             std::thread::sleep(interval);
             print_stats(&skel)?;
@@ -105,42 +178,213 @@ fn main() -> Result<()> {
         }
     }
 
+    if args.pin_maps {
+        unpin_maps(&args.pin_dir);
+    }
+
     info!("scx_morpheus exiting");
     Ok(())
 }
 
-fn print_stats(skel: &ScxMorpheusSkel) -> Result<()> {
-    // Read stats from each CPU and aggregate
-    let stats_map = &skel.maps().stats_map;
+/// Pin `worker_tid_map` and `scb_map` under `pin_dir` via `BPF_OBJ_PIN`.
+///
+/// This gives out-of-process workers (a separate Rust process, or the Python
+/// module) a stable path to find these maps through
+/// `BpfMaps::from_pinned_paths`, instead of relying on the raw fds being
+/// inherited across a fork.
+fn pin_maps(skel: &ScxMorpheusSkel, pin_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(pin_dir)
+        .with_context(|| format!("failed to create pin dir {:?}", pin_dir))?;
+
+    let tid_map_path = pin_dir.join("worker_tid_map");
+    let scb_map_path = pin_dir.join("scb_map");
+
+    skel.maps()
+        .worker_tid_map
+        .pin(&tid_map_path)
+        .with_context(|| format!("failed to pin worker_tid_map at {:?}", tid_map_path))?;
+
+    skel.maps()
+        .scb_map
+        .pin(&scb_map_path)
+        .with_context(|| format!("failed to pin scb_map at {:?}", scb_map_path))?;
+
+    Ok(())
+}
+
+/// Unpin previously pinned maps and remove the pin directory.
+fn unpin_maps(pin_dir: &Path) {
+    if let Err(e) = std::fs::remove_dir_all(pin_dir) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            error!("failed to remove pin dir {:?}: {}", pin_dir, e);
+        }
+    }
+}
+
+/// Write `config` into slot 0 of `config_map`.
+fn write_config(skel: &ScxMorpheusSkel, config: &MorpheusConfig) -> Result<()> {
     let key: u32 = 0;
-    let key_bytes = key.to_ne_bytes();
+    let value = unsafe {
+        std::slice::from_raw_parts(
+            config as *const MorpheusConfig as *const u8,
+            std::mem::size_of::<MorpheusConfig>(),
+        )
+    };
 
-    let mut total_hints = 0u64;
-    let mut total_dropped = 0u64;
-    let mut total_escalations = 0u64;
-    let mut total_blocked = 0u64;
-    let mut total_ticks = 0u64;
-
-    // Note: In a real implementation, we'd iterate over all CPUs
-    // For now, just read the first entry as a placeholder
-    if let Ok(value) = stats_map.lookup(&key_bytes, libbpf_rs::MapFlags::ANY) {
-        if let Some(bytes) = value {
-            // Parse the stats structure from bytes
-            // This is a simplified version; real code would use proper deserialization
-            if bytes.len() >= 40 {
-                total_hints = u64::from_ne_bytes(bytes[0..8].try_into().unwrap());
-                total_dropped = u64::from_ne_bytes(bytes[8..16].try_into().unwrap());
-                total_escalations = u64::from_ne_bytes(bytes[16..24].try_into().unwrap());
-                total_blocked = u64::from_ne_bytes(bytes[24..32].try_into().unwrap());
-                total_ticks = u64::from_ne_bytes(bytes[32..40].try_into().unwrap());
+    skel.maps()
+        .config_map
+        .update(&key.to_ne_bytes(), value, libbpf_rs::MapFlags::ANY)
+        .context("config_map update failed")
+}
+
+/// Parse a `key=value` config file into a `MorpheusConfig`.
+///
+/// Recognized keys: `slice_ms`, `grace_ms`, `debug`. Unknown keys and blank
+/// lines are ignored so the file can carry comments and future settings.
+fn reload_config(path: &Path) -> Result<MorpheusConfig> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))?;
+
+    let mut config = MorpheusConfig::default();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "slice_ms" => {
+                config.slice_ns = value.parse::<u64>()?.saturating_mul(1_000_000);
+            }
+            "grace_ms" => {
+                config.grace_period_ns = value.parse::<u64>()?.saturating_mul(1_000_000);
+            }
+            "debug" => {
+                config.debug_mode = value.parse::<bool>()? as u32;
+            }
+            other => {
+                tracing::warn!("ignoring unknown config key {:?} in {:?}", other, path);
             }
         }
     }
 
+    Ok(config)
+}
+
+/// Spawn a dedicated thread that drains `hint_ringbuf` and forwards each hint
+/// to the telemetry writer, alongside the pressure level of the worker the
+/// hint targeted.
+fn spawn_hint_drain(skel: &ScxMorpheusSkel, telemetry: telemetry::TelemetryHandle) -> Result<()> {
+    // `MapHandle` owns a cloned map fd decoupled from `skel`'s lifetime, so
+    // the ring buffer can be polled on its own thread.
+    let hint_map = libbpf_rs::MapHandle::try_from(&skel.maps().hint_ringbuf)
+        .context("failed to clone hint_ringbuf handle")?;
+    let scb_map = libbpf_rs::MapHandle::try_from(&skel.maps().scb_map)
+        .context("failed to clone scb_map handle")?;
+    let tid_map = libbpf_rs::MapHandle::try_from(&skel.maps().worker_tid_map)
+        .context("failed to clone worker_tid_map handle")?;
+
+    std::thread::Builder::new()
+        .name("morpheus-hint-drain".to_string())
+        .spawn(move || {
+            let mut builder = libbpf_rs::RingBufferBuilder::new();
+            let result = builder
+                .add(&hint_map, move |data: &[u8]| -> i32 {
+                    if data.len() < std::mem::size_of::<MorpheusHint>() {
+                        return 0;
+                    }
+                    let hint =
+                        unsafe { std::ptr::read_unaligned(data.as_ptr() as *const MorpheusHint) };
+                    let pressure = pressure_for_tid(&tid_map, &scb_map, hint.target_tid);
+                    telemetry.record_hint(&hint, pressure);
+                    0
+                })
+                .and_then(|b| b.build());
+
+            match result {
+                Ok(rb) => loop {
+                    if let Err(e) = rb.poll(Duration::from_millis(100)) {
+                        error!("hint ring buffer poll failed: {}", e);
+                        return;
+                    }
+                },
+                Err(e) => error!("failed to build hint ring buffer: {}", e),
+            }
+        })
+        .context("failed to spawn hint drain thread")?;
+
+    Ok(())
+}
+
+/// Look up the kernel pressure level for the worker currently registered
+/// under `tid`, via `worker_tid_map` -> `scb_map`. Returns 0 if the thread
+/// isn't a registered worker.
+fn pressure_for_tid(tid_map: &libbpf_rs::MapHandle, scb_map: &libbpf_rs::MapHandle, tid: u32) -> u32 {
+    const PRESSURE_OFFSET: usize = std::mem::size_of::<u64>() * 2; // after preempt_seq, budget_remaining_ns
+
+    let Ok(Some(worker_id_bytes)) = tid_map.lookup(&tid.to_ne_bytes(), libbpf_rs::MapFlags::ANY)
+    else {
+        return 0;
+    };
+    let Ok(worker_id_bytes) = <[u8; 4]>::try_from(worker_id_bytes.as_slice()) else {
+        return 0;
+    };
+
+    // scb_map is a BPF_MAP_TYPE_ARRAY keyed by logical worker index, not by
+    // a byte offset into the mmap'd region (that addressing is only valid
+    // for the direct-mmap path in scb.rs) - so the lookup key is
+    // worker_id_bytes itself, unmultiplied by scb_size.
+    let Ok(Some(scb_bytes)) = scb_map.lookup(&worker_id_bytes, libbpf_rs::MapFlags::ANY) else {
+        return 0;
+    };
+
+    scb_bytes
+        .get(PRESSURE_OFFSET..PRESSURE_OFFSET + 4)
+        .and_then(|b| <[u8; 4]>::try_from(b).ok())
+        .map(u32::from_ne_bytes)
+        .unwrap_or(0)
+}
+
+fn print_stats(skel: &ScxMorpheusSkel) -> Result<()> {
+    let stats = read_stats(skel).context("failed to read stats_map")?;
+
     info!(
         "stats: ticks={} hints={} dropped={} escalations={} blocked={}",
-        total_ticks, total_hints, total_dropped, total_escalations, total_blocked
+        stats.ticks, stats.hints, stats.dropped, stats.escalations, stats.blocked
     );
 
     Ok(())
 }
+
+/// Aggregate scheduler stats across all CPUs.
+///
+/// `stats_map` is a `BPF_MAP_TYPE_PERCPU_ARRAY`, so `lookup_percpu` returns one
+/// value buffer per CPU. Each CPU keeps an independent copy, so we must sum the
+/// fields across the full `Vec<Vec<u8>>` rather than trusting a single slot.
+fn read_stats(skel: &ScxMorpheusSkel) -> Result<MorpheusStats> {
+    let stats_map = &skel.maps().stats_map;
+    let key: u32 = 0;
+    let key_bytes = key.to_ne_bytes();
+
+    let mut total = MorpheusStats::default();
+
+    if let Some(per_cpu) = stats_map
+        .lookup_percpu(&key_bytes, libbpf_rs::MapFlags::ANY)
+        .context("lookup_percpu failed")?
+    {
+        for cpu_bytes in &per_cpu {
+            if cpu_bytes.len() < std::mem::size_of::<MorpheusStats>() {
+                continue;
+            }
+            let cpu_stats = unsafe {
+                std::ptr::read_unaligned(cpu_bytes.as_ptr() as *const MorpheusStats)
+            };
+            total.accumulate(&cpu_stats);
+        }
+    }
+
+    Ok(total)
+}
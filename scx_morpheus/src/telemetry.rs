@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: GPL-2.0-only
+// Copyright (C) 2024 Ankit Kumar Pandey <ankitkpandey1@gmail.com>
+
+//! Streaming telemetry server
+//!
+//! Opt-in observability beyond the periodic `print_stats` line: a background
+//! thread drains the hint ring buffer and emits one fixed-size, binary
+//! record per hint to a Unix domain socket (or a TCP socket with
+//! `TCP_NODELAY`), so external tools can consume scheduler events live
+//! instead of waiting on the stats interval.
+
+use anyhow::{Context, Result};
+use morpheus_common::{HintReason, MorpheusHint};
+use std::io::{BufWriter, Write};
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Flush the buffered writer once this many records have accumulated, even
+/// if the stats-interval tick hasn't fired yet.
+const FLUSH_THRESHOLD: usize = 256;
+
+/// One telemetry event, written to the socket as fixed-size little-endian
+/// fields (not `#[repr(C)]`-transmuted, so the wire format doesn't depend on
+/// host struct padding).
+#[derive(Debug, Clone, Copy)]
+pub struct TelemetryEvent {
+    /// Monotonic microsecond timestamp, counted from this process's first event
+    pub timestamp_us: u64,
+    /// Target worker thread id
+    pub worker_id: u32,
+    /// Why the kernel requested a yield
+    pub hint_reason: HintReason,
+    /// Kernel pressure level (0-100) at the time of the hint
+    pub pressure_level: u32,
+}
+
+impl TelemetryEvent {
+    const WIRE_SIZE: usize = 8 + 4 + 4 + 4;
+
+    fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        out.write_all(&self.timestamp_us.to_le_bytes())?;
+        out.write_all(&self.worker_id.to_le_bytes())?;
+        out.write_all(&(self.hint_reason as u32).to_le_bytes())?;
+        out.write_all(&self.pressure_level.to_le_bytes())
+    }
+
+    fn from_hint(hint: &MorpheusHint, pressure_level: u32) -> Self {
+        Self {
+            timestamp_us: now_us(),
+            worker_id: hint.target_tid,
+            hint_reason: HintReason::try_from(hint.reason).unwrap_or(HintReason::Pressure),
+            pressure_level,
+        }
+    }
+}
+
+fn now_us() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_micros() as u64
+}
+
+/// Where to stream telemetry events.
+#[derive(Debug, Clone)]
+pub enum TelemetryTarget {
+    Unix(std::path::PathBuf),
+    Tcp(std::net::SocketAddr),
+}
+
+impl std::str::FromStr for TelemetryTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(addr) = s.strip_prefix("tcp://") {
+            Ok(Self::Tcp(addr.parse().context("invalid TCP address")?))
+        } else {
+            Ok(Self::Unix(std::path::PathBuf::from(s)))
+        }
+    }
+}
+
+enum Sink {
+    Unix(BufWriter<UnixStream>),
+    Tcp(BufWriter<TcpStream>),
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Sink::Unix(w) => w.write(buf),
+            Sink::Tcp(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Sink::Unix(w) => w.flush(),
+            Sink::Tcp(w) => w.flush(),
+        }
+    }
+}
+
+fn connect(target: &TelemetryTarget) -> Result<Sink> {
+    match target {
+        TelemetryTarget::Unix(path) => {
+            let stream = UnixStream::connect(path)
+                .with_context(|| format!("failed to connect to {:?}", path))?;
+            Ok(Sink::Unix(BufWriter::new(stream)))
+        }
+        TelemetryTarget::Tcp(addr) => {
+            let stream =
+                TcpStream::connect(addr).with_context(|| format!("failed to connect to {}", addr))?;
+            // Disabling Nagle matters here: without it small event frames get
+            // coalesced and delayed, defeating the point of real-time tracing.
+            stream.set_nodelay(true).context("failed to set TCP_NODELAY")?;
+            Ok(Sink::Tcp(BufWriter::new(stream)))
+        }
+    }
+}
+
+/// Handle to the telemetry producer side, used by the ring buffer callback
+/// to push events; flushing is done by the writer thread.
+pub struct TelemetryHandle {
+    tx: Sender<TelemetryEvent>,
+}
+
+impl TelemetryHandle {
+    /// Queue an event for the background writer thread.
+    pub fn record_hint(&self, hint: &MorpheusHint, pressure_level: u32) {
+        let event = TelemetryEvent::from_hint(hint, pressure_level);
+        if self.tx.send(event).is_err() {
+            warn!("telemetry writer thread gone, dropping event");
+        }
+    }
+}
+
+/// Spawn the background thread that connects to `target` and drains events
+/// pushed through the returned `TelemetryHandle`.
+///
+/// The writer buffers events and flushes on whichever comes first: the
+/// buffer filling past `FLUSH_THRESHOLD` records, or `flush_interval`
+/// elapsing (intended to match the loader's `--stats-interval` tick).
+pub fn spawn(target: TelemetryTarget, flush_interval: Duration) -> Result<TelemetryHandle> {
+    let sink = connect(&target).context("failed to connect telemetry sink")?;
+    let (tx, rx): (Sender<TelemetryEvent>, Receiver<TelemetryEvent>) = std::sync::mpsc::channel();
+
+    std::thread::Builder::new()
+        .name("morpheus-telemetry".to_string())
+        .spawn(move || run_writer(sink, rx, flush_interval))
+        .context("failed to spawn telemetry thread")?;
+
+    info!("telemetry streaming started");
+    Ok(TelemetryHandle { tx })
+}
+
+fn run_writer(mut sink: Sink, rx: Receiver<TelemetryEvent>, flush_interval: Duration) {
+    let mut pending = 0usize;
+    let mut last_flush = Instant::now();
+
+    loop {
+        match rx.recv_timeout(flush_interval) {
+            Ok(event) => {
+                if let Err(e) = event.write_to(&mut sink) {
+                    warn!("telemetry write failed, stopping stream: {}", e);
+                    return;
+                }
+                pending += 1;
+                if pending >= FLUSH_THRESHOLD || last_flush.elapsed() >= flush_interval {
+                    let _ = sink.flush();
+                    pending = 0;
+                    last_flush = Instant::now();
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                if pending > 0 && last_flush.elapsed() >= flush_interval {
+                    let _ = sink.flush();
+                    pending = 0;
+                    last_flush = Instant::now();
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                let _ = sink.flush();
+                return;
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+const _WIRE_SIZE_CHECK: usize = TelemetryEvent::WIRE_SIZE;